@@ -0,0 +1,211 @@
+//! A versioned, self-describing file format for trained models.
+//!
+//! A model file starts with a header of one field per line:
+//!
+//! ```text
+//! ax-model 1
+//! game tic-tac-toe
+//! kind network
+//! meta algorithm backprop
+//! meta epochs 100
+//! model
+//! ```
+//!
+//! The first line holds the version of the format, followed by the game the
+//! model was trained for, what kind of model it is and any number of `meta`
+//! lines describing how it was trained. Everything after the `model` line
+//! is the model itself, as written by [`Network::write`],
+//! [`Genome::write`] or [`Recurrent::write`].
+
+use std::error;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+
+use crate::ai::neat::Genome;
+use crate::ai::nn::Network;
+use crate::ai::rnn::Recurrent;
+
+/// The version of the format written by [`ModelFile::write`].
+pub const VERSION: u32 = 1;
+
+/// A trained model of any kind.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Trained {
+    Network(Network),
+    Genome(Genome),
+    Recurrent(Recurrent),
+}
+
+impl Trained {
+    /// Returns the name of this kind of model, as used in model files.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Trained::Network(_) => "network",
+            Trained::Genome(_) => "genome",
+            Trained::Recurrent(_) => "recurrent",
+        }
+    }
+}
+
+impl fmt::Display for Trained {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Trained::Network(net) => write!(f, "network {}", net),
+            Trained::Genome(genome) => write!(f, "genome with {}", genome),
+            Trained::Recurrent(net) => write!(f, "recurrent network {}", net),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    /// The file isn't a valid model file.
+    Format(String),
+    /// The file was written with a version of the format this one can't read.
+    Version(u32),
+    /// The model was trained for another game.
+    Game {
+        expected: String,
+        found: String,
+    },
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "{}", err),
+            Error::Format(msg) => write!(f, "not a valid model file: {}", msg),
+            Error::Version(v) => write!(
+                f,
+                "model file version {} is not supported, expected version {}",
+                v, VERSION
+            ),
+            Error::Game { expected, found } => {
+                write!(f, "model was trained for {}, not {}", found, expected)
+            }
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+/// A trained model along with the game it was trained for and how.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ModelFile {
+    pub game: String,
+    /// Describes how the model was trained, e.g. the algorithm and its settings.
+    pub metadata: Vec<(String, String)>,
+    pub model: Trained,
+}
+
+impl ModelFile {
+    pub fn new(game: &str, model: Trained) -> Self {
+        Self {
+            game: game.to_string(),
+            metadata: Vec::new(),
+            model,
+        }
+    }
+
+    /// Records how the model was trained.
+    pub fn meta(mut self, key: &str, value: impl fmt::Display) -> Self {
+        self.metadata.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    /// Looks up a value recorded with [`ModelFile::meta`].
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.metadata
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Checks the model was trained for `game`.
+    pub fn for_game(self, game: &str) -> Result<Self, Error> {
+        if self.game != game {
+            return Err(Error::Game {
+                expected: game.to_string(),
+                found: self.game,
+            });
+        }
+        Ok(self)
+    }
+
+    pub fn write(&self, mut out: impl Write) -> io::Result<()> {
+        writeln!(out, "ax-model {}", VERSION)?;
+        writeln!(out, "game {}", self.game)?;
+        writeln!(out, "kind {}", self.model.kind())?;
+        for (key, value) in &self.metadata {
+            writeln!(out, "meta {} {}", key, value)?;
+        }
+        writeln!(out, "model")?;
+        match &self.model {
+            Trained::Network(net) => net.write(out),
+            Trained::Genome(genome) => genome.write(out),
+            Trained::Recurrent(net) => net.write(out),
+        }
+    }
+
+    pub fn read(mut input: impl BufRead) -> Result<Self, Error> {
+        let mut next_line = || -> Result<String, Error> {
+            let mut line = String::new();
+            if input.read_line(&mut line)? == 0 {
+                return Err(Error::Format("unexpected end of header".to_string()));
+            }
+            Ok(line.trim_end().to_string())
+        };
+
+        let line = next_line()?;
+        let version = line
+            .strip_prefix("ax-model ")
+            .ok_or_else(|| Error::Format("missing ax-model header".to_string()))?;
+        let version = version
+            .parse()
+            .map_err(|_| Error::Format(format!("invalid version {:?}", version)))?;
+        if version != VERSION {
+            return Err(Error::Version(version));
+        }
+
+        let (mut game, mut kind, mut metadata) = (None, None, Vec::new());
+        loop {
+            let line = next_line()?;
+            if line == "model" {
+                break;
+            }
+            let (field, value) = line.split_at(line.find(' ').unwrap_or(line.len()));
+            let value = value.trim_start();
+            match field {
+                "game" => game = Some(value.to_string()),
+                "kind" => kind = Some(value.to_string()),
+                "meta" => {
+                    let (key, value) = value.split_at(value.find(' ').unwrap_or(value.len()));
+                    metadata.push((key.to_string(), value.trim_start().to_string()));
+                }
+                _ => return Err(Error::Format(format!("unknown field {:?}", field))),
+            }
+        }
+
+        let game = game.ok_or_else(|| Error::Format("missing game".to_string()))?;
+        let model = match kind.as_deref() {
+            Some("network") => Trained::Network(Network::read(&mut input)?),
+            Some("genome") => Trained::Genome(Genome::read(&mut input)?),
+            Some("recurrent") => Trained::Recurrent(Recurrent::read(&mut input)?),
+            Some(kind) => return Err(Error::Format(format!("unknown kind {:?}", kind))),
+            None => return Err(Error::Format("missing kind".to_string())),
+        };
+
+        Ok(Self {
+            game,
+            metadata,
+            model,
+        })
+    }
+}
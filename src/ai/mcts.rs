@@ -0,0 +1,265 @@
+use std::fmt;
+use std::marker::PhantomData;
+use std::time::Instant;
+
+use rand::{Rng, RngCore};
+use tracing::debug;
+
+use crate as ax;
+use crate::ai::Budget;
+
+/// Picks which state to move to during the playouts of [`Mcts`].
+pub trait Rollout<S> {
+    /// Returns the index of the child of `state` to move to.
+    fn choose(&mut self, state: &S, children: &[S], rng: &mut dyn RngCore) -> usize;
+}
+
+/// Playouts made of uniformly random moves.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct Uniform;
+
+impl<S> Rollout<S> for Uniform {
+    fn choose(&mut self, _state: &S, children: &[S], rng: &mut dyn RngCore) -> usize {
+        rng.gen_range(0..children.len())
+    }
+}
+
+impl<S, F> Rollout<S> for F
+where
+    F: FnMut(&S, &[S], &mut dyn RngCore) -> usize,
+{
+    fn choose(&mut self, state: &S, children: &[S], rng: &mut dyn RngCore) -> usize {
+        self(state, children, rng)
+    }
+}
+
+struct Node<S> {
+    state: S,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    /// Children which haven't been added to the tree yet.
+    untried: Vec<S>,
+    /// Seat of the player who moved into this state.
+    mover: usize,
+    visits: u32,
+    /// Sum of the rewards of `mover` over every playout through this state.
+    reward: f64,
+}
+
+/// This AI uses Monte Carlo Tree Search, guided by UCT, to determine its moves.
+///
+/// Instead of a hueristic it plays many games to the end from each
+/// position and favours the moves which won most often.
+pub struct Mcts<E, S, R, P = Uniform>
+where
+    E: fmt::Debug,
+    S: ax::FiniteState<E>,
+    R: Rng,
+    P: Rollout<S>,
+{
+    _e: PhantomData<E>,
+    _d: PhantomData<S>,
+    rng: R,
+    rollout: P,
+    budget: Budget,
+    exploration: f64,
+    iterations: u64,
+}
+
+impl<E, S, R> Mcts<E, S, R>
+where
+    E: fmt::Debug,
+    S: ax::FiniteState<E>,
+    R: Rng,
+{
+    /// Creates an MCTS player which runs 10,000 playouts per turn.
+    pub fn new(rng: R) -> Self {
+        Self {
+            _e: PhantomData,
+            _d: PhantomData,
+            rng,
+            rollout: Uniform,
+            budget: Budget::Nodes(10_000),
+            exploration: std::f64::consts::SQRT_2,
+            iterations: 0,
+        }
+    }
+}
+
+impl<E, S, R, P> Mcts<E, S, R, P>
+where
+    E: fmt::Debug,
+    S: ax::FiniteState<E>,
+    R: Rng,
+    P: Rollout<S>,
+{
+    /// Limit how long each turn is thought about. Every node is one playout,
+    /// and at least one playout is run whatever the budget.
+    pub fn budget(mut self, budget: Budget) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    /// Set how eagerly rarely visited moves are explored, `√2` by default.
+    pub fn exploration(mut self, c: f64) -> Self {
+        self.exploration = c;
+        self
+    }
+
+    /// Use a different policy to pick moves during playouts.
+    pub fn rollout<P2: Rollout<S>>(self, rollout: P2) -> Mcts<E, S, R, P2> {
+        Mcts {
+            _e: PhantomData,
+            _d: PhantomData,
+            rng: self.rng,
+            rollout,
+            budget: self.budget,
+            exploration: self.exploration,
+            iterations: self.iterations,
+        }
+    }
+
+    /// Returns how many playouts the last turn took.
+    pub fn iterations(&self) -> u64 {
+        self.iterations
+    }
+
+    fn exhausted(&self, started: Instant) -> bool {
+        match self.budget {
+            Budget::Nodes(n) => self.iterations >= n,
+            Budget::Time(d) => started.elapsed() >= d,
+        }
+    }
+
+    fn children(state: &S) -> Vec<S> {
+        state.next_possibilities().unwrap_or_default()
+    }
+
+    fn add(tree: &mut Vec<Node<S>>, state: S, parent: Option<usize>, mover: usize) -> usize {
+        let untried = Self::children(&state);
+        tree.push(Node {
+            state,
+            parent,
+            children: Vec::new(),
+            untried,
+            mover,
+            visits: 0,
+            reward: 0.0,
+        });
+        tree.len() - 1
+    }
+
+    /// Picks the child with the best upper confidence bound.
+    fn select(&self, tree: &[Node<S>], node: usize) -> usize {
+        let ln_n = f64::from(tree[node].visits).ln();
+        let uct = |child: &Node<S>| {
+            let n = f64::from(child.visits);
+            child.reward / n + self.exploration * (ln_n / n).sqrt()
+        };
+
+        let mut children = tree[node].children.iter().copied();
+        let first = children.next().expect("node should have children");
+        children.fold(first, |best, c| {
+            if uct(&tree[c]) > uct(&tree[best]) {
+                c
+            } else {
+                best
+            }
+        })
+    }
+
+    /// Plays the game out from `state` and returns its outcome.
+    fn playout(&mut self, state: &S) -> ax::Outcome {
+        let mut current: Option<S> = None;
+        loop {
+            let state = current.as_ref().unwrap_or(state);
+            if let ax::Status::Terminal(outcome) = state.status().unwrap() {
+                return outcome;
+            }
+
+            let mut children = Self::children(state);
+            if children.is_empty() {
+                return ax::Outcome::Draw;
+            }
+            let i = self.rollout.choose(state, &children, &mut self.rng);
+            current = Some(children.swap_remove(i));
+        }
+    }
+}
+
+/// Reward of `player` for an outcome, from `0` for a loss to `1` for a win.
+fn reward(outcome: &ax::Outcome, player: usize) -> f64 {
+    match outcome.winner() {
+        Some(p) if p == player => 1.0,
+        Some(_) => 0.0,
+        None => 0.5,
+    }
+}
+
+impl<E, S, R, P> ax::Player<S> for Mcts<E, S, R, P>
+where
+    E: fmt::Debug,
+    S: ax::FiniteState<E>,
+    R: Rng,
+    P: Rollout<S>,
+{
+    fn take_turn(&mut self, state: S) -> Result<S, ax::PlayerError<S>> {
+        let started = Instant::now();
+        self.iterations = 0;
+
+        let mut tree = Vec::new();
+        let root = Self::add(&mut tree, state, None, usize::MAX);
+        match tree[root].untried.len() {
+            0 => panic!("node is terminal already"),
+            1 => return Ok(tree.swap_remove(root).untried.remove(0)),
+            _ => {}
+        }
+
+        // Always run one playout, so the root has a child to pick.
+        while self.iterations == 0 || !self.exhausted(started) {
+            self.iterations += 1;
+
+            // Selection
+            let mut node = root;
+            while tree[node].untried.is_empty() && !tree[node].children.is_empty() {
+                node = self.select(&tree, node);
+            }
+
+            // Expansion
+            if !tree[node].untried.is_empty() {
+                let i = self.rng.gen_range(0..tree[node].untried.len());
+                let child = tree[node].untried.swap_remove(i);
+                let mover = tree[node].state.current_player();
+                let leaf = Self::add(&mut tree, child, Some(node), mover);
+                tree[node].children.push(leaf);
+                node = leaf;
+            }
+
+            // Simulation
+            let outcome = self.playout(&tree[node].state);
+
+            // Backpropagation
+            let mut current = Some(node);
+            while let Some(n) = current {
+                tree[n].visits += 1;
+                tree[n].reward += reward(&outcome, tree[n].mover);
+                current = tree[n].parent;
+            }
+        }
+
+        let best = tree[root]
+            .children
+            .iter()
+            .copied()
+            .max_by_key(|c| tree[*c].visits)
+            .expect("root should have been expanded");
+        debug!(
+            iterations = self.iterations,
+            visits = tree[best].visits,
+            reward = tree[best].reward / f64::from(tree[best].visits),
+            elapsed = ?started.elapsed(),
+            "mcts picked a move"
+        );
+        Ok(tree.swap_remove(best).state)
+    }
+}
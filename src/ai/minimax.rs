@@ -1,79 +1,435 @@
 use std::fmt;
 use std::marker::PhantomData;
+use std::mem;
+use std::time::Instant;
+
+use tracing::debug;
 
 use crate as ax;
+use crate::ai::{Bound, Budget, Entry, TranspositionTable};
+
+/// Wins further away than this many moves all score the same.
+const MAX_PLY: usize = 27;
+
+/// Score of a win `ply` moves away. Hueristics should stay well below this.
+fn win(ply: usize) -> i8 {
+    i8::MAX - ply.min(MAX_PLY) as i8
+}
+
+/// Scores of wins are relative to the root of the search, but are stored
+/// relative to the state they were found in, so they stay correct when the
+/// same state is reached at a different ply.
+fn to_table(score: i8, ply: usize) -> i8 {
+    let ply = ply.min(MAX_PLY) as i8;
+    if score >= win(MAX_PLY) {
+        score.saturating_add(ply)
+    } else if score <= -win(MAX_PLY) {
+        score.saturating_sub(ply)
+    } else {
+        score
+    }
+}
+
+fn from_table(score: i8, ply: usize) -> i8 {
+    let ply = ply.min(MAX_PLY) as i8;
+    if score >= win(MAX_PLY) {
+        (score - ply).max(win(MAX_PLY))
+    } else if score <= -win(MAX_PLY) {
+        (score + ply).min(-win(MAX_PLY))
+    } else {
+        score
+    }
+}
+
+/// Finds the hash of a state.
+type Hasher<S> = fn(&S) -> u64;
+
+/// Sorts moves before they are searched.
+type MoveOrder<S, M> = Box<dyn Fn(&S, &mut [M])>;
+
+/// Statistics about the most recent search done by [`Negamax`].
+#[derive(Debug, Default, PartialEq, Copy, Clone)]
+pub struct SearchStats {
+    /// Number of nodes visited.
+    pub nodes: u64,
+    /// Deepest search which ran to completion.
+    pub depth: usize,
+    /// Score of the chosen move.
+    pub score: i8,
+    /// Number of states found in the transposition table.
+    pub tt_hits: u64,
+    /// Number of states missing from the transposition table.
+    pub tt_misses: u64,
+}
 
 /// This AI uses the Negamax algorithm to determine its moves.
-#[derive(Debug)]
-pub struct Negamax<P, E, S, F>
+pub struct Negamax<E, S, F>
 where
-    P: fmt::Display + PartialEq + Default + Copy,
     E: fmt::Debug,
-    S: ax::State<E>,
+    S: ax::Game<E>,
     F: Fn(&S) -> i8,
 {
     _e: PhantomData<E>,
     _d: PhantomData<S>,
-    piece: P,
+    player: usize,
     max_depth: usize,
     hue: F,
+    pruning: bool,
+    order: Option<MoveOrder<S, S::Move>>,
+    budget: Option<Budget>,
+    table: Option<(TranspositionTable<S::Move>, Hasher<S>)>,
+    started: Option<Instant>,
+    cutoff: bool,
+    stats: SearchStats,
 }
 
-impl<E, P, S, F> Negamax<P, E, S, F>
+impl<E, S, F> Negamax<E, S, F>
 where
-    P: fmt::Display + PartialEq + Default + Copy,
     E: fmt::Debug,
-    S: ax::FiniteState<E>,
+    S: ax::Game<E>,
     F: Fn(&S) -> i8,
 {
     /// Provide a max depth and hueristic for Negamax to use when scoring possible moves.
-    pub fn with_hueristic(piece: P, max_depth: usize, f: F) -> Self {
+    ///
+    /// `player` is the seat Negamax plays in, as reported by [`ax::Outcome`], and the
+    /// hueristic should score states from that player's point of view.
+    pub fn with_hueristic(player: usize, max_depth: usize, f: F) -> Self {
         Self {
             _e: PhantomData,
             _d: PhantomData,
-            piece,
+            player,
             max_depth,
             hue: f,
+            pruning: true,
+            order: None,
+            budget: None,
+            table: None,
+            started: None,
+            cutoff: false,
+            stats: SearchStats::default(),
+        }
+    }
+
+    /// Enable or disable alpha-beta pruning, which is enabled by default.
+    ///
+    /// Pruning never changes which move is picked, it only skips
+    /// parts of the tree which can't affect the result.
+    pub fn pruning(mut self, enabled: bool) -> Self {
+        self.pruning = enabled;
+        self
+    }
+
+    /// Sort moves before they are searched. Searching good moves first
+    /// lets alpha-beta pruning skip more of the tree.
+    pub fn order_moves(mut self, f: impl Fn(&S, &mut [S::Move]) + 'static) -> Self {
+        self.order = Some(Box::new(f));
+        self
+    }
+
+    /// Search with iterative deepening, one level deeper at a time up to
+    /// the max depth, until the budget runs out. The best move of the
+    /// deepest finished search is played.
+    pub fn budget(mut self, budget: Budget) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    /// Remember searched states in a transposition table with room for
+    /// `capacity` entries, so states reached through different move orders
+    /// are only searched once. The table is kept between turns.
+    pub fn transposition_table(mut self, capacity: usize) -> Self
+    where
+        S: ax::StateHash,
+    {
+        self.table = Some((TranspositionTable::new(capacity), S::state_hash));
+        self
+    }
+
+    /// Returns the transposition table, if one is used.
+    pub fn table(&self) -> Option<&TranspositionTable<S::Move>> {
+        self.table.as_ref().map(|(table, _)| table)
+    }
+
+    /// Returns statistics about the last search.
+    pub fn stats(&self) -> SearchStats {
+        self.stats
+    }
+
+    fn exhausted(&self) -> bool {
+        match (self.budget, self.started) {
+            (Some(Budget::Nodes(n)), _) => self.stats.nodes >= n,
+            (Some(Budget::Time(d)), Some(started)) => started.elapsed() >= d,
+            _ => false,
         }
     }
 
-    fn negamax(&self, node: &S, depth: usize, color: i8) -> i8 {
-        if depth == 0 || node.status().unwrap() == ax::Status::Terminal {
-            return color * (self.hue)(node);
+    /// Returns the moves to search, with `first` moved to the front.
+    fn legal_moves(&self, node: &S, first: Option<S::Move>) -> Vec<S::Move> {
+        let mut moves = node.legal_moves();
+        if let Some(order) = &self.order {
+            order(node, &mut moves);
         }
+        if let Some(i) = first.and_then(|mv| moves.iter().position(|m| *m == mv)) {
+            moves[..=i].rotate_right(1);
+        }
+        moves
+    }
+
+    fn probe(&mut self, node: &S) -> Option<(u64, Option<Entry<S::Move>>)> {
+        let (table, hash) = self.table.as_mut()?;
+        let hash = hash(node);
+        let entry = table.get(hash).cloned();
+        if entry.is_some() {
+            self.stats.tt_hits += 1;
+        } else {
+            self.stats.tt_misses += 1;
+        }
+        Some((hash, entry))
+    }
 
-        -1 * node
-            .next_possibilities()
-            .expect("node is terminal")
-            .into_iter()
-            .map(|n| self.negamax(&n, depth - 1, -1 * color))
-            .max()
-            .unwrap()
+    fn store(&mut self, entry: Entry<S::Move>) {
+        if let Some((table, _)) = self.table.as_mut() {
+            table.insert(entry);
+        }
+    }
+
+    /// Score a state from the point of view of `self.player`.
+    fn score(&self, node: &S, status: ax::Status, ply: usize) -> i8 {
+        match status {
+            ax::Status::Terminal(outcome) => match outcome.winner() {
+                Some(p) if p == self.player => win(ply),
+                Some(_) => -win(ply),
+                None => 0,
+            },
+            ax::Status::Valid => (self.hue)(node),
+        }
+    }
+
+    /// Search `node` and score it from the point of view of the player whose turn it is.
+    ///
+    /// Returns `None` if the budget ran out before the search finished.
+    fn negamax(
+        &mut self,
+        node: &mut S,
+        depth: usize,
+        ply: usize,
+        mut alpha: i8,
+        beta: i8,
+    ) -> Option<i8> {
+        if self.exhausted() {
+            return None;
+        }
+        self.stats.nodes += 1;
+
+        let player = node.current_player();
+
+        let status = node.status().unwrap();
+        if depth == 0 || status.is_terminal() {
+            self.cutoff |= !status.is_terminal();
+            let score = self.score(node, status, ply);
+            return Some(if player == self.player { score } else { -score });
+        }
+
+        let (alpha_orig, mut beta) = (alpha, beta);
+
+        let probe = self.probe(node);
+        let mut first = None;
+        if let Some((_, Some(entry))) = &probe {
+            first = entry.best.clone();
+            if entry.depth >= depth {
+                let score = from_table(entry.score, ply);
+                match entry.bound {
+                    Bound::Exact => {
+                        self.cutoff |= entry.cutoff;
+                        return Some(score);
+                    }
+                    Bound::Lower => alpha = alpha.max(score),
+                    Bound::Upper => beta = beta.min(score),
+                }
+                if alpha >= beta {
+                    self.cutoff |= entry.cutoff;
+                    return Some(score);
+                }
+            }
+        }
+
+        // Start afresh, so afterwards `self.cutoff` tells whether anything
+        // below this node was cut off, which its table entry has to remember.
+        let outer = mem::replace(&mut self.cutoff, false);
+
+        let mut best = -i8::MAX;
+        let mut best_move = None;
+        for mv in self.legal_moves(node, first) {
+            node.apply(mv.clone());
+            let score = self.search(node, depth - 1, ply + 1, player, alpha, beta);
+            node.undo(mv.clone());
+
+            let score = score?;
+            if best_move.is_none() || score > best {
+                best = score;
+                best_move = Some(mv);
+            }
+            if self.pruning {
+                alpha = alpha.max(best);
+                if alpha >= beta {
+                    break;
+                }
+            }
+        }
+
+        let below = self.cutoff;
+        self.cutoff |= outer;
+        if let Some((hash, _)) = probe {
+            let bound = if best <= alpha_orig {
+                Bound::Upper
+            } else if best >= beta {
+                Bound::Lower
+            } else {
+                Bound::Exact
+            };
+            self.store(Entry {
+                hash,
+                depth,
+                score: to_table(best, ply),
+                bound,
+                best: best_move,
+                cutoff: below,
+            });
+        }
+        Some(best)
+    }
+
+    /// Search `node` and score it from the point of view of `player`.
+    fn search(
+        &mut self,
+        node: &mut S,
+        depth: usize,
+        ply: usize,
+        player: usize,
+        alpha: i8,
+        beta: i8,
+    ) -> Option<i8> {
+        if node.current_player() == player {
+            self.negamax(node, depth, ply, alpha, beta)
+        } else {
+            self.negamax(node, depth, ply, -beta, -alpha).map(|s| -s)
+        }
+    }
+
+    /// Find the best move from `state`, looking `depth` moves past it.
+    fn search_root(&mut self, state: &mut S, depth: usize) -> Option<(S::Move, i8)> {
+        let mut alpha = -i8::MAX;
+
+        let probe = self.probe(state);
+        let first = probe
+            .as_ref()
+            .and_then(|(_, entry)| entry.as_ref())
+            .and_then(|entry| entry.best.clone());
+
+        let mut best: Option<(S::Move, i8)> = None;
+        for mv in self.legal_moves(state, first) {
+            state.apply(mv.clone());
+            let score = self.search(state, depth, 1, self.player, alpha, i8::MAX);
+            state.undo(mv.clone());
+
+            let score = score?;
+            match best {
+                Some((_, s)) if s >= score => {}
+                _ => best = Some((mv, score)),
+            }
+            if self.pruning {
+                alpha = alpha.max(score);
+            }
+        }
+
+        if let (Some((hash, _)), Some((mv, score))) = (probe, &best) {
+            self.store(Entry {
+                hash,
+                depth: depth + 1,
+                score: *score,
+                bound: Bound::Exact,
+                best: Some(mv.clone()),
+                cutoff: self.cutoff,
+            });
+        }
+        best
     }
 }
 
-impl<E, S, P, F> ax::Player<S> for Negamax<P, E, S, F>
+impl<E, S, F> ax::Player<S> for Negamax<E, S, F>
 where
-    P: fmt::Display + PartialEq + Default + Copy,
     E: fmt::Debug,
-    S: ax::FiniteState<E>,
+    S: ax::Game<E>,
     F: Fn(&S) -> i8,
 {
-    fn take_turn(&mut self, state: S) -> S {
-        let posses = state
-            .next_possibilities()
-            .expect("node is terminal already");
-
-        let best = posses
-            .into_iter()
-            .map(|n| {
-                let score = self.negamax(&n, self.max_depth, -1);
-                (n, score)
-            })
-            .max_by(|(_, s1), (_, s2)| s1.cmp(s2))
-            .map(|(s, _)| s)
-            .expect("no maximum found");
+    fn take_turn(&mut self, state: S) -> Result<S, ax::PlayerError<S>> {
+        let mut state = state;
 
-        best
+        self.stats = SearchStats::default();
+        self.started = Some(Instant::now());
+
+        let best = match self.budget {
+            None => {
+                self.stats.depth = self.max_depth;
+                self.search_root(&mut state, self.max_depth)
+            }
+            Some(_) => {
+                let mut best = None;
+                for depth in 0..=self.max_depth {
+                    self.cutoff = false;
+                    match self.search_root(&mut state, depth) {
+                        Some(found) => {
+                            best = Some(found);
+                            self.stats.depth = depth;
+                        }
+                        None => break,
+                    }
+                    // Nothing was left unexplored, so searching deeper won't change anything.
+                    if !self.cutoff {
+                        break;
+                    }
+                }
+                best.or_else(|| {
+                    let mv = self.legal_moves(&state, None).into_iter().next()?;
+                    Some((mv, 0))
+                })
+            }
+        };
+
+        let (mv, score) = best.expect("node is terminal already");
+        self.stats.score = score;
+        debug!(
+            player = self.player,
+            nodes = self.stats.nodes,
+            depth = self.stats.depth,
+            score,
+            tt_hits = self.stats.tt_hits,
+            tt_misses = self.stats.tt_misses,
+            elapsed = ?self.started.map(|s| s.elapsed()),
+            "negamax picked a move"
+        );
+        state.apply(mv);
+        Ok(state)
+    }
+}
+
+impl<E, S, F> fmt::Debug for Negamax<E, S, F>
+where
+    E: fmt::Debug,
+    S: ax::Game<E>,
+    F: Fn(&S) -> i8,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Negamax")
+            .field("player", &self.player)
+            .field("max_depth", &self.max_depth)
+            .field("pruning", &self.pruning)
+            .field("budget", &self.budget)
+            .field(
+                "table",
+                &self.table.as_ref().map(|(table, _)| table.capacity()),
+            )
+            .field("stats", &self.stats)
+            .finish()
     }
 }
@@ -1,5 +1,26 @@
 //! Provide AI implementations for games built with the Ax engine.
 
+use std::time::Duration;
+
+pub mod file;
+mod mcts;
 mod minimax;
+mod model;
+pub mod neat;
+pub mod nn;
+pub mod rnn;
+mod table;
+
+pub use mcts::{Mcts, Rollout, Uniform};
+pub use minimax::{Negamax, SearchStats};
+pub use model::{Features, Model, ModelPlayer};
+pub use table::{Bound, Entry, TranspositionTable};
 
-pub use minimax::Negamax;
+/// Limits how much work a search may do before it has to make a move.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Budget {
+    /// Stop searching once this much time has passed.
+    Time(Duration),
+    /// Stop searching once this many nodes have been visited.
+    Nodes(u64),
+}
@@ -0,0 +1,82 @@
+use std::marker::PhantomData;
+
+use rand::Rng;
+use tracing::debug;
+
+use crate as ax;
+
+/// Maps the features of a state to outputs, such as how good the state is.
+pub trait Model {
+    fn forward(&self, input: &[f64]) -> Vec<f64>;
+}
+
+impl<M: Model + ?Sized> Model for &M {
+    fn forward(&self, input: &[f64]) -> Vec<f64> {
+        (**self).forward(input)
+    }
+}
+
+/// Describes a state from the point of view of a seat, for use as the input of a [`Model`].
+pub type Features<S> = fn(&S, usize) -> Vec<f64>;
+
+/// This AI scores every state it can move to with a [`Model`] and
+/// moves to the one with the highest first output.
+pub struct ModelPlayer<E, S, M, R> {
+    _e: PhantomData<E>,
+    model: M,
+    features: Features<S>,
+    epsilon: f64,
+    rng: R,
+}
+
+impl<E, S, M, R> ModelPlayer<E, S, M, R>
+where
+    S: ax::FiniteState<E>,
+    M: Model,
+    R: Rng,
+{
+    pub fn new(model: M, features: Features<S>, rng: R) -> Self {
+        Self {
+            _e: PhantomData,
+            model,
+            features,
+            epsilon: 0.0,
+            rng,
+        }
+    }
+
+    /// Move randomly instead with a probability of `epsilon`.
+    pub fn explore(mut self, epsilon: f64) -> Self {
+        self.epsilon = epsilon;
+        self
+    }
+}
+
+impl<E, S, M, R> ax::Player<S> for ModelPlayer<E, S, M, R>
+where
+    S: ax::FiniteState<E>,
+    M: Model,
+    R: Rng,
+{
+    fn take_turn(&mut self, state: S) -> Result<S, ax::PlayerError<S>> {
+        let seat = state.current_player();
+        let mut children = state.next_possibilities().expect("game is over");
+
+        let i = if self.rng.gen_bool(self.epsilon) {
+            debug!(seat, "model explored a random move");
+            self.rng.gen_range(0..children.len())
+        } else {
+            let scores: Vec<f64> = children
+                .iter()
+                .map(|c| self.model.forward(&(self.features)(c, seat))[0])
+                .collect();
+            let best = (0..children.len())
+                .max_by(|a, b| scores[*a].total_cmp(&scores[*b]))
+                .expect("game is over");
+            debug!(seat, score = scores[best], "model picked a move");
+            best
+        };
+
+        Ok(children.swap_remove(i))
+    }
+}
@@ -0,0 +1,784 @@
+//! NeuroEvolution of Augmenting Topologies (NEAT), which evolves both
+//! the weights and the structure of nueral networks.
+//!
+//! A [`Population`] of [`Genome`]s is scored with a fitness function each
+//! generation. Genomes are grouped into species by how similar their
+//! structure is, so new structure gets a few generations to prove itself
+//! before it has to compete with the whole population.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::ai::Model;
+
+/// Settings for evolving a [`Population`].
+#[derive(Debug, PartialEq, Clone)]
+pub struct Config {
+    /// Number of genomes in each generation.
+    pub population: usize,
+    /// Genomes closer than this are put in the same species.
+    pub compatibility_threshold: f64,
+    /// Weight of excess genes when measuring the distance between genomes.
+    pub excess: f64,
+    /// Weight of disjoint genes when measuring the distance between genomes.
+    pub disjoint: f64,
+    /// Weight of the average weight difference of matching genes.
+    pub weight: f64,
+    /// Chance of a genome's weights being mutated.
+    pub weight_mutation: f64,
+    /// Chance of a mutated weight being replaced instead of nudged.
+    pub weight_reset: f64,
+    /// Largest amount a weight is nudged by.
+    pub weight_power: f64,
+    /// Chance of adding a connection.
+    pub add_connection: f64,
+    /// Chance of splitting a connection with a new node.
+    pub add_node: f64,
+    /// Chance of an offspring having two parents.
+    pub crossover: f64,
+    /// Fraction of each species allowed to have offspring.
+    pub survival: f64,
+    /// Species which haven't improved for this many generations die out.
+    pub stagnation: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            population: 150,
+            compatibility_threshold: 3.0,
+            excess: 1.0,
+            disjoint: 1.0,
+            weight: 0.4,
+            weight_mutation: 0.8,
+            weight_reset: 0.1,
+            weight_power: 0.5,
+            add_connection: 0.05,
+            add_node: 0.03,
+            crossover: 0.75,
+            survival: 0.2,
+            stagnation: 15,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum NodeKind {
+    Input,
+    /// An input which is always `1`.
+    Bias,
+    Hidden,
+    Output,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct NodeGene {
+    pub id: usize,
+    pub kind: NodeKind,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ConnectionGene {
+    /// Identifies the structural change which added this connection.
+    pub innovation: usize,
+    pub from: usize,
+    pub to: usize,
+    pub weight: f64,
+    pub enabled: bool,
+}
+
+/// Hands out innovation numbers and node ids, so the same structural
+/// change gets the same numbers no matter which genome makes it.
+#[derive(Debug, Default)]
+pub struct Innovations {
+    connections: HashMap<(usize, usize), usize>,
+    splits: HashMap<usize, usize>,
+    next_innovation: usize,
+    next_node: usize,
+}
+
+impl Innovations {
+    fn connection(&mut self, from: usize, to: usize) -> usize {
+        let next = &mut self.next_innovation;
+        *self.connections.entry((from, to)).or_insert_with(|| {
+            *next += 1;
+            *next - 1
+        })
+    }
+
+    fn split(&mut self, innovation: usize) -> usize {
+        let next = &mut self.next_node;
+        *self.splits.entry(innovation).or_insert_with(|| {
+            *next += 1;
+            *next - 1
+        })
+    }
+}
+
+/// Describes the structure and weights of a nueral network.
+///
+/// Node ids `0..inputs` are the inputs, followed by a bias node and then
+/// the outputs. Hidden nodes come after that. Connections never form a
+/// cycle, so every genome describes a feed-forward network.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Genome {
+    inputs: usize,
+    outputs: usize,
+    nodes: Vec<NodeGene>,
+    /// Sorted by innovation number.
+    connections: Vec<ConnectionGene>,
+}
+
+impl Genome {
+    /// Creates a genome with every input connected to every output.
+    fn initial(
+        inputs: usize,
+        outputs: usize,
+        innovations: &mut Innovations,
+        rng: &mut impl Rng,
+    ) -> Self {
+        let mut nodes: Vec<NodeGene> = (0..inputs)
+            .map(|id| NodeGene {
+                id,
+                kind: NodeKind::Input,
+            })
+            .collect();
+        nodes.push(NodeGene {
+            id: inputs,
+            kind: NodeKind::Bias,
+        });
+        nodes.extend((0..outputs).map(|o| NodeGene {
+            id: inputs + 1 + o,
+            kind: NodeKind::Output,
+        }));
+        innovations.next_node = innovations.next_node.max(inputs + 1 + outputs);
+
+        let mut connections = Vec::new();
+        for from in 0..=inputs {
+            for to in inputs + 1..=inputs + outputs {
+                connections.push(ConnectionGene {
+                    innovation: innovations.connection(from, to),
+                    from,
+                    to,
+                    weight: rng.gen_range(-1.0..1.0),
+                    enabled: true,
+                });
+            }
+        }
+
+        Self {
+            inputs,
+            outputs,
+            nodes,
+            connections,
+        }
+    }
+
+    /// Returns the number of inputs and outputs.
+    pub fn sizes(&self) -> (usize, usize) {
+        (self.inputs, self.outputs)
+    }
+
+    pub fn nodes(&self) -> &[NodeGene] {
+        &self.nodes
+    }
+
+    pub fn connections(&self) -> &[ConnectionGene] {
+        &self.connections
+    }
+
+    fn kind(&self, id: usize) -> Option<NodeKind> {
+        self.nodes.iter().find(|n| n.id == id).map(|n| n.kind)
+    }
+
+    /// Checks if `to` is reachable from `from`. Disabled connections count
+    /// too, so crossover can safely enable them again.
+    fn reaches(&self, from: usize, to: usize) -> bool {
+        let mut stack = vec![from];
+        let mut seen = vec![from];
+        while let Some(node) = stack.pop() {
+            if node == to {
+                return true;
+            }
+            for c in self.connections.iter().filter(|c| c.from == node) {
+                if !seen.contains(&c.to) {
+                    seen.push(c.to);
+                    stack.push(c.to);
+                }
+            }
+        }
+        false
+    }
+
+    fn mutate(&mut self, config: &Config, innovations: &mut Innovations, rng: &mut impl Rng) {
+        if rng.gen_bool(config.weight_mutation) {
+            for c in &mut self.connections {
+                if rng.gen_bool(config.weight_reset) {
+                    c.weight = rng.gen_range(-1.0..1.0);
+                } else {
+                    c.weight += rng.gen_range(-config.weight_power..config.weight_power);
+                }
+            }
+        }
+        if rng.gen_bool(config.add_connection) {
+            self.add_connection(innovations, rng);
+        }
+        if rng.gen_bool(config.add_node) {
+            self.add_node(innovations, rng);
+        }
+    }
+
+    fn add_connection(&mut self, innovations: &mut Innovations, rng: &mut impl Rng) {
+        for _ in 0..20 {
+            let from = self.nodes.choose(rng).unwrap();
+            let to = self.nodes.choose(rng).unwrap();
+            if from.kind == NodeKind::Output
+                || matches!(to.kind, NodeKind::Input | NodeKind::Bias)
+                || from.id == to.id
+            {
+                continue;
+            }
+            let (from, to) = (from.id, to.id);
+            if self
+                .connections
+                .iter()
+                .any(|c| c.from == from && c.to == to)
+                || self.reaches(to, from)
+            {
+                continue;
+            }
+
+            self.insert(ConnectionGene {
+                innovation: innovations.connection(from, to),
+                from,
+                to,
+                weight: rng.gen_range(-1.0..1.0),
+                enabled: true,
+            });
+            return;
+        }
+    }
+
+    fn add_node(&mut self, innovations: &mut Innovations, rng: &mut impl Rng) {
+        let enabled: Vec<usize> = (0..self.connections.len())
+            .filter(|i| self.connections[*i].enabled)
+            .collect();
+        let i = match enabled.choose(rng) {
+            Some(i) => *i,
+            None => return,
+        };
+
+        let old = self.connections[i].clone();
+        let id = innovations.split(old.innovation);
+        if self.kind(id).is_some() {
+            return;
+        }
+
+        self.connections[i].enabled = false;
+        self.nodes.push(NodeGene {
+            id,
+            kind: NodeKind::Hidden,
+        });
+        self.insert(ConnectionGene {
+            innovation: innovations.connection(old.from, id),
+            from: old.from,
+            to: id,
+            weight: 1.0,
+            enabled: true,
+        });
+        self.insert(ConnectionGene {
+            innovation: innovations.connection(id, old.to),
+            from: id,
+            to: old.to,
+            weight: old.weight,
+            enabled: true,
+        });
+    }
+
+    fn insert(&mut self, gene: ConnectionGene) {
+        let i = self
+            .connections
+            .partition_point(|c| c.innovation < gene.innovation);
+        self.connections.insert(i, gene);
+    }
+
+    /// Breeds two genomes. Matching genes are picked at random from either
+    /// parent, the rest are inherited from the fitter parent only.
+    fn crossover(fitter: &Genome, other: &Genome, rng: &mut impl Rng) -> Genome {
+        let connections = fitter
+            .connections
+            .iter()
+            .map(|gene| {
+                match other
+                    .connections
+                    .binary_search_by_key(&gene.innovation, |c| c.innovation)
+                {
+                    Ok(i) => {
+                        let theirs = &other.connections[i];
+                        let mut child = if rng.gen_bool(0.5) {
+                            gene.clone()
+                        } else {
+                            theirs.clone()
+                        };
+                        child.enabled = (gene.enabled && theirs.enabled) || rng.gen_bool(0.25);
+                        child
+                    }
+                    Err(_) => gene.clone(),
+                }
+            })
+            .collect();
+
+        Genome {
+            inputs: fitter.inputs,
+            outputs: fitter.outputs,
+            nodes: fitter.nodes.clone(),
+            connections,
+        }
+    }
+
+    /// Measures how different the structure and weights of two genomes are.
+    pub fn distance(&self, other: &Genome, config: &Config) -> f64 {
+        let (a, b) = (&self.connections, &other.connections);
+        let (mut i, mut j) = (0, 0);
+        let (mut disjoint, mut matching, mut weights) = (0, 0, 0.0);
+        while i < a.len() && j < b.len() {
+            match a[i].innovation.cmp(&b[j].innovation) {
+                std::cmp::Ordering::Equal => {
+                    matching += 1;
+                    weights += (a[i].weight - b[j].weight).abs();
+                    i += 1;
+                    j += 1;
+                }
+                std::cmp::Ordering::Less => {
+                    disjoint += 1;
+                    i += 1;
+                }
+                std::cmp::Ordering::Greater => {
+                    disjoint += 1;
+                    j += 1;
+                }
+            }
+        }
+        let excess = (a.len() - i) + (b.len() - j);
+
+        let n = a.len().max(b.len());
+        let n = if n < 20 { 1.0 } else { n as f64 };
+        let weights = if matching > 0 {
+            weights / matching as f64
+        } else {
+            0.0
+        };
+
+        config.excess * excess as f64 / n
+            + config.disjoint * disjoint as f64 / n
+            + config.weight * weights
+    }
+
+    /// Builds the network described by this genome.
+    pub fn network(&self) -> FeedForward {
+        let index: HashMap<usize, usize> = self
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(i, n)| (n.id, i))
+            .collect();
+
+        // Kahn's algorithm, so every node comes after the nodes feeding it.
+        let enabled: Vec<&ConnectionGene> = self.connections.iter().filter(|c| c.enabled).collect();
+        let mut incoming = vec![0; self.nodes.len()];
+        for c in &enabled {
+            incoming[index[&c.to]] += 1;
+        }
+        let mut ready: Vec<usize> = (0..self.nodes.len())
+            .filter(|i| incoming[*i] == 0)
+            .collect();
+        let mut order = Vec::with_capacity(self.nodes.len());
+        while let Some(node) = ready.pop() {
+            order.push(node);
+            for c in enabled.iter().filter(|c| index[&c.from] == node) {
+                let to = index[&c.to];
+                incoming[to] -= 1;
+                if incoming[to] == 0 {
+                    ready.push(to);
+                }
+            }
+        }
+
+        let steps = order
+            .into_iter()
+            .filter(|i| matches!(self.nodes[*i].kind, NodeKind::Hidden | NodeKind::Output))
+            .map(|i| {
+                let id = self.nodes[i].id;
+                let sources = enabled
+                    .iter()
+                    .filter(|c| c.to == id)
+                    .map(|c| (index[&c.from], c.weight))
+                    .collect();
+                (i, sources)
+            })
+            .collect();
+
+        FeedForward {
+            size: self.nodes.len(),
+            inputs: (0..self.inputs).map(|id| index[&id]).collect(),
+            bias: index[&self.inputs],
+            outputs: (0..self.outputs)
+                .map(|o| index[&(self.inputs + 1 + o)])
+                .collect(),
+            steps,
+        }
+    }
+
+    /// Writes the genome as text, see [`Genome::read`].
+    pub fn write(&self, mut out: impl Write) -> io::Result<()> {
+        writeln!(out, "genome {} {}", self.inputs, self.outputs)?;
+        for n in self.nodes.iter().filter(|n| n.kind == NodeKind::Hidden) {
+            writeln!(out, "node {}", n.id)?;
+        }
+        for c in &self.connections {
+            writeln!(
+                out,
+                "connection {} {} {} {} {}",
+                c.innovation, c.from, c.to, c.weight, c.enabled as u8
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Reads a genome written by [`Genome::write`].
+    ///
+    /// The first line holds the number of inputs and outputs. It is
+    /// followed by a line for each hidden node and then a line for each
+    /// connection, holding its innovation number, the nodes it connects,
+    /// its weight and whether it is enabled.
+    pub fn read(input: impl BufRead) -> io::Result<Self> {
+        let mut lines = input.lines();
+
+        let header = lines
+            .next()
+            .unwrap_or_else(|| Err(invalid("unexpected end of genome")))?;
+        let sizes: Vec<usize> = header
+            .strip_prefix("genome ")
+            .ok_or_else(|| invalid("expected genome"))?
+            .split_whitespace()
+            .map(|s| s.parse().map_err(|_| invalid("invalid genome size")))
+            .collect::<io::Result<_>>()?;
+        let (inputs, outputs) = match sizes[..] {
+            [inputs, outputs] => (inputs, outputs),
+            _ => return Err(invalid("expected number of inputs and outputs")),
+        };
+
+        let mut genome = Genome {
+            inputs,
+            outputs,
+            nodes: Vec::new(),
+            connections: Vec::new(),
+        };
+        genome.nodes.extend((0..inputs).map(|id| NodeGene {
+            id,
+            kind: NodeKind::Input,
+        }));
+        genome.nodes.push(NodeGene {
+            id: inputs,
+            kind: NodeKind::Bias,
+        });
+        genome.nodes.extend((0..outputs).map(|o| NodeGene {
+            id: inputs + 1 + o,
+            kind: NodeKind::Output,
+        }));
+
+        for line in lines {
+            let line = line?;
+            let mut fields = line.split_whitespace();
+            match fields.next() {
+                Some("node") => {
+                    let id = parse(fields.next())?;
+                    if genome.kind(id).is_some() {
+                        return Err(invalid("duplicate node"));
+                    }
+                    genome.nodes.push(NodeGene {
+                        id,
+                        kind: NodeKind::Hidden,
+                    });
+                }
+                Some("connection") => {
+                    let gene = ConnectionGene {
+                        innovation: parse(fields.next())?,
+                        from: parse(fields.next())?,
+                        to: parse(fields.next())?,
+                        weight: parse(fields.next())?,
+                        enabled: parse::<u8>(fields.next())? == 1,
+                    };
+                    if genome.kind(gene.from).is_none() || genome.kind(gene.to).is_none() {
+                        return Err(invalid("connection to unknown node"));
+                    }
+                    genome.insert(gene);
+                }
+                None => {}
+                Some(_) => return Err(invalid("expected node or connection")),
+            }
+        }
+
+        if genome.network().steps.len() != genome.nodes.len() - inputs - 1 {
+            return Err(invalid("connections form a cycle"));
+        }
+        Ok(genome)
+    }
+}
+
+impl fmt::Display for Genome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} nodes, {} connections",
+            self.nodes.len(),
+            self.connections.iter().filter(|c| c.enabled).count()
+        )
+    }
+}
+
+/// The network described by a [`Genome`], ready to be evaluated.
+#[derive(Debug, Clone)]
+pub struct FeedForward {
+    size: usize,
+    inputs: Vec<usize>,
+    bias: usize,
+    outputs: Vec<usize>,
+    /// Nodes in the order they are evaluated, with their weighted inputs.
+    steps: Vec<(usize, Vec<(usize, f64)>)>,
+}
+
+impl Model for FeedForward {
+    fn forward(&self, input: &[f64]) -> Vec<f64> {
+        let mut values = vec![0.0; self.size];
+        for (i, x) in self.inputs.iter().zip(input) {
+            values[*i] = *x;
+        }
+        values[self.bias] = 1.0;
+
+        for (node, sources) in &self.steps {
+            let sum: f64 = sources.iter().map(|(s, w)| values[*s] * w).sum();
+            values[*node] = sum.tanh();
+        }
+
+        self.outputs.iter().map(|o| values[*o]).collect()
+    }
+}
+
+struct Species {
+    representative: Genome,
+    members: Vec<usize>,
+    best: f64,
+    stagnant: usize,
+}
+
+/// Summary of one generation of a [`Population`].
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Generation {
+    pub generation: usize,
+    pub best: f64,
+    pub mean: f64,
+    pub species: usize,
+}
+
+/// A population of genomes evolving over many generations.
+pub struct Population {
+    config: Config,
+    genomes: Vec<Genome>,
+    species: Vec<Species>,
+    innovations: Innovations,
+    generation: usize,
+    champion: Option<(Genome, f64)>,
+}
+
+impl Population {
+    /// Creates a population of genomes with the given number of inputs and outputs.
+    ///
+    /// `config.population` must be at least `1`.
+    pub fn new(inputs: usize, outputs: usize, config: Config, rng: &mut impl Rng) -> Self {
+        assert!(config.population > 0, "a population needs genomes");
+        let mut innovations = Innovations::default();
+        let genomes = (0..config.population)
+            .map(|_| Genome::initial(inputs, outputs, &mut innovations, rng))
+            .collect();
+
+        Self {
+            config,
+            genomes,
+            species: Vec::new(),
+            innovations,
+            generation: 0,
+            champion: None,
+        }
+    }
+
+    /// Returns the genomes of the current generation.
+    pub fn genomes(&self) -> &[Genome] {
+        &self.genomes
+    }
+
+    /// Returns the fittest genome of every generation so far.
+    pub fn champion(&self) -> Option<&Genome> {
+        self.champion.as_ref().map(|(g, _)| g)
+    }
+
+    /// Scores every genome of the current generation with `fitness`, which
+    /// should never be negative, and breeds the next generation from them.
+    pub fn evolve(
+        &mut self,
+        mut fitness: impl FnMut(&Genome) -> f64,
+        rng: &mut impl Rng,
+    ) -> Generation {
+        let scores: Vec<f64> = self.genomes.iter().map(|g| fitness(g).max(0.0)).collect();
+
+        let (best, best_score) = scores
+            .iter()
+            .copied()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .expect("population should not be empty");
+        if self.champion.as_ref().is_none_or(|(_, s)| best_score >= *s) {
+            self.champion = Some((self.genomes[best].clone(), best_score));
+        }
+
+        self.speciate(&scores, best);
+
+        let stats = Generation {
+            generation: self.generation,
+            best: best_score,
+            mean: scores.iter().sum::<f64>() / scores.len() as f64,
+            species: self.species.len(),
+        };
+
+        self.genomes = self.reproduce(&scores, rng);
+        for species in &mut self.species {
+            species.members.clear();
+        }
+        self.generation += 1;
+
+        stats
+    }
+
+    /// Sorts genomes into species and drops species which stopped improving.
+    fn speciate(&mut self, scores: &[f64], best: usize) {
+        for species in &mut self.species {
+            species.members.clear();
+        }
+        for (i, genome) in self.genomes.iter().enumerate() {
+            let config = &self.config;
+            match self.species.iter_mut().find(|s| {
+                s.representative.distance(genome, config) < config.compatibility_threshold
+            }) {
+                Some(species) => species.members.push(i),
+                None => self.species.push(Species {
+                    representative: genome.clone(),
+                    members: vec![i],
+                    best: 0.0,
+                    stagnant: 0,
+                }),
+            }
+        }
+        self.species.retain(|s| !s.members.is_empty());
+
+        for species in &mut self.species {
+            let top = species
+                .members
+                .iter()
+                .map(|m| scores[*m])
+                .fold(0.0, f64::max);
+            if top > species.best {
+                species.best = top;
+                species.stagnant = 0;
+            } else {
+                species.stagnant += 1;
+            }
+        }
+        let limit = self.config.stagnation;
+        self.species
+            .retain(|s| s.stagnant <= limit || s.members.contains(&best));
+    }
+
+    /// Breeds the next generation, giving each species a share of the
+    /// offspring proportional to its average fitness.
+    fn reproduce(&mut self, scores: &[f64], rng: &mut impl Rng) -> Vec<Genome> {
+        let size = self.config.population;
+
+        let shares: Vec<f64> = self
+            .species
+            .iter()
+            .map(|s| s.members.iter().map(|m| scores[*m]).sum::<f64>() / s.members.len() as f64)
+            .collect();
+        let total: f64 = shares.iter().sum();
+        let exact: Vec<f64> = shares
+            .iter()
+            .map(|s| {
+                if total > 0.0 {
+                    s / total * size as f64
+                } else {
+                    size as f64 / shares.len() as f64
+                }
+            })
+            .collect();
+
+        let mut counts: Vec<usize> = exact.iter().map(|e| e.floor() as usize).collect();
+        let mut remainder: Vec<usize> = (0..counts.len()).collect();
+        remainder.sort_by(|a, b| {
+            (exact[*b] - exact[*b].floor()).total_cmp(&(exact[*a] - exact[*a].floor()))
+        });
+        for i in remainder
+            .into_iter()
+            .cycle()
+            .take(size - counts.iter().sum::<usize>())
+        {
+            counts[i] += 1;
+        }
+
+        let mut next = Vec::with_capacity(size);
+        for (species, count) in self.species.iter_mut().zip(counts) {
+            let mut members = species.members.clone();
+            members.sort_by(|a, b| scores[*b].total_cmp(&scores[*a]));
+            let parents =
+                &members[..((members.len() as f64 * self.config.survival).ceil() as usize).max(1)];
+
+            for child in 0..count {
+                // The best genome of each species survives unchanged.
+                if child == 0 {
+                    next.push(self.genomes[parents[0]].clone());
+                    continue;
+                }
+
+                let a = *parents.choose(rng).unwrap();
+                let mut genome = if parents.len() > 1 && rng.gen_bool(self.config.crossover) {
+                    let b = *parents.choose(rng).unwrap();
+                    let (fitter, other) = if scores[a] >= scores[b] {
+                        (a, b)
+                    } else {
+                        (b, a)
+                    };
+                    Genome::crossover(&self.genomes[fitter], &self.genomes[other], rng)
+                } else {
+                    self.genomes[a].clone()
+                };
+                genome.mutate(&self.config, &mut self.innovations, rng);
+                next.push(genome);
+            }
+
+            species.representative = self.genomes[*species.members.choose(rng).unwrap()].clone();
+        }
+        next
+    }
+}
+
+fn parse<T: std::str::FromStr>(field: Option<&str>) -> io::Result<T> {
+    field
+        .and_then(|f| f.parse().ok())
+        .ok_or_else(|| invalid("invalid field"))
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
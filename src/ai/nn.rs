@@ -0,0 +1,437 @@
+//! A small dense (feed-forward) nueral network.
+
+use std::fmt;
+use std::io::{self, BufRead, Write};
+use std::str::FromStr;
+
+use rand::Rng;
+
+use crate::ai::Model;
+
+/// Applied to the weighted sum going into each node.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Activation {
+    Linear,
+    Tanh,
+    Sigmoid,
+    Relu,
+}
+
+impl Activation {
+    fn apply(self, x: f64) -> f64 {
+        match self {
+            Activation::Linear => x,
+            Activation::Tanh => x.tanh(),
+            Activation::Sigmoid => 1.0 / (1.0 + (-x).exp()),
+            Activation::Relu => x.max(0.0),
+        }
+    }
+
+    /// The derivative, in terms of the activation's output `y`.
+    fn derivative(self, y: f64) -> f64 {
+        match self {
+            Activation::Linear => 1.0,
+            Activation::Tanh => 1.0 - y * y,
+            Activation::Sigmoid => y * (1.0 - y),
+            Activation::Relu => {
+                if y > 0.0 {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for Activation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Activation::Linear => write!(f, "linear"),
+            Activation::Tanh => write!(f, "tanh"),
+            Activation::Sigmoid => write!(f, "sigmoid"),
+            Activation::Relu => write!(f, "relu"),
+        }
+    }
+}
+
+impl FromStr for Activation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "linear" => Ok(Activation::Linear),
+            "tanh" => Ok(Activation::Tanh),
+            "sigmoid" => Ok(Activation::Sigmoid),
+            "relu" => Ok(Activation::Relu),
+            _ => Err(format!("unknown activation {:?}", s)),
+        }
+    }
+}
+
+/// Measures how far the outputs of a network are from their targets.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Loss {
+    MeanSquared,
+    /// Binary cross-entropy of each output, which should lie between `0`
+    /// and `1` like the outputs of [`Activation::Sigmoid`].
+    CrossEntropy,
+}
+
+/// Keeps cross-entropy finite for outputs of exactly `0` or `1`.
+const CLAMP: f64 = 1e-7;
+
+impl Loss {
+    fn loss(self, output: &[f64], target: &[f64]) -> f64 {
+        let sum: f64 = output
+            .iter()
+            .zip(target)
+            .map(|(y, t)| match self {
+                Loss::MeanSquared => (y - t).powi(2),
+                Loss::CrossEntropy => {
+                    let y = y.clamp(CLAMP, 1.0 - CLAMP);
+                    -(t * y.ln() + (1.0 - t) * (1.0 - y).ln())
+                }
+            })
+            .sum();
+        sum / target.len() as f64
+    }
+
+    /// The gradient of the loss with respect to each output.
+    fn gradient(self, output: &[f64], target: &[f64]) -> Vec<f64> {
+        let n = target.len() as f64;
+        output
+            .iter()
+            .zip(target)
+            .map(|(y, t)| match self {
+                Loss::MeanSquared => 2.0 * (y - t) / n,
+                Loss::CrossEntropy => {
+                    let y = y.clamp(CLAMP, 1.0 - CLAMP);
+                    (y - t) / (y * (1.0 - y)) / n
+                }
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for Loss {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Loss::MeanSquared => write!(f, "mse"),
+            Loss::CrossEntropy => write!(f, "cross-entropy"),
+        }
+    }
+}
+
+impl FromStr for Loss {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "mse" => Ok(Loss::MeanSquared),
+            "cross-entropy" => Ok(Loss::CrossEntropy),
+            _ => Err(format!("unknown loss {:?}", s)),
+        }
+    }
+}
+
+/// How weights are moved along their gradient.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Optimizer {
+    /// Stochastic gradient descent.
+    Sgd { rate: f64 },
+    /// Adam, which adapts the step size of each weight using running
+    /// averages of its gradient and squared gradient.
+    Adam { rate: f64, beta1: f64, beta2: f64 },
+}
+
+impl Optimizer {
+    pub fn sgd(rate: f64) -> Self {
+        Optimizer::Sgd { rate }
+    }
+
+    /// Adam with the usual decay rates.
+    pub fn adam(rate: f64) -> Self {
+        Optimizer::Adam {
+            rate,
+            beta1: 0.9,
+            beta2: 0.999,
+        }
+    }
+
+    /// Returns how much to subtract from a weight, given its gradient,
+    /// its moments and how many steps have been taken.
+    pub(crate) fn step(self, grad: f64, moments: &mut (f64, f64), steps: u64) -> f64 {
+        match self {
+            Optimizer::Sgd { rate } => rate * grad,
+            Optimizer::Adam { rate, beta1, beta2 } => {
+                moments.0 = beta1 * moments.0 + (1.0 - beta1) * grad;
+                moments.1 = beta2 * moments.1 + (1.0 - beta2) * grad * grad;
+                let m = moments.0 / (1.0 - beta1.powf(steps as f64));
+                let v = moments.1 / (1.0 - beta2.powf(steps as f64));
+                rate * m / (v.sqrt() + 1e-8)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Optimizer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Optimizer::Sgd { rate } => write!(f, "sgd {}", rate),
+            Optimizer::Adam { rate, .. } => write!(f, "adam {}", rate),
+        }
+    }
+}
+
+/// A fully connected layer. Each row of `weights` holds the
+/// weights of one output, with its bias in front.
+#[derive(Debug, PartialEq, Clone)]
+struct Layer {
+    inputs: usize,
+    activation: Activation,
+    weights: Vec<Vec<f64>>,
+    /// Running averages kept by the optimizer, one for each weight.
+    moments: Vec<Vec<(f64, f64)>>,
+}
+
+impl Layer {
+    fn new(inputs: usize, outputs: usize, rng: &mut impl Rng) -> Self {
+        let limit = 1.0 / (inputs as f64).sqrt();
+        let weights = (0..outputs)
+            .map(|_| (0..=inputs).map(|_| rng.gen_range(-limit..limit)).collect())
+            .collect();
+        Self::with_weights(inputs, Activation::Tanh, weights)
+    }
+
+    fn with_weights(inputs: usize, activation: Activation, weights: Vec<Vec<f64>>) -> Self {
+        Self {
+            inputs,
+            activation,
+            moments: vec![vec![(0.0, 0.0); inputs + 1]; weights.len()],
+            weights,
+        }
+    }
+
+    fn forward(&self, input: &[f64]) -> Vec<f64> {
+        self.weights
+            .iter()
+            .map(|w| {
+                let sum: f64 = w[1..].iter().zip(input).map(|(w, x)| w * x).sum();
+                self.activation.apply(w[0] + sum)
+            })
+            .collect()
+    }
+}
+
+/// A nueral network of fully connected layers, trained with backpropagation.
+///
+/// By default every layer uses `tanh` and training minimises the mean
+/// squared error with stochastic gradient descent.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Network {
+    layers: Vec<Layer>,
+    loss: Loss,
+    optimizer: Optimizer,
+    steps: u64,
+}
+
+impl Network {
+    /// Creates a network with randomly initialised weights.
+    ///
+    /// `sizes` lists the number of nodes in each layer, starting with
+    /// the inputs and ending with the outputs.
+    pub fn new(sizes: &[usize], rng: &mut impl Rng) -> Self {
+        assert!(sizes.len() >= 2, "a network needs inputs and outputs");
+        Self::with_layers(
+            sizes
+                .windows(2)
+                .map(|w| Layer::new(w[0], w[1], rng))
+                .collect(),
+        )
+    }
+
+    fn with_layers(layers: Vec<Layer>) -> Self {
+        Self {
+            layers,
+            loss: Loss::MeanSquared,
+            optimizer: Optimizer::sgd(0.01),
+            steps: 0,
+        }
+    }
+
+    /// Use `hidden` for every hidden layer and `output` for the output layer.
+    pub fn activations(mut self, hidden: Activation, output: Activation) -> Self {
+        let last = self.layers.len() - 1;
+        for (i, layer) in self.layers.iter_mut().enumerate() {
+            layer.activation = if i == last { output } else { hidden };
+        }
+        self
+    }
+
+    pub fn loss(mut self, loss: Loss) -> Self {
+        self.loss = loss;
+        self
+    }
+
+    pub fn optimizer(mut self, optimizer: Optimizer) -> Self {
+        self.optimizer = optimizer;
+        self
+    }
+
+    /// Returns the number of nodes in each layer.
+    pub fn sizes(&self) -> Vec<usize> {
+        let mut sizes = vec![self.layers[0].inputs];
+        sizes.extend(self.layers.iter().map(|l| l.weights.len()));
+        sizes
+    }
+
+    /// Computes the outputs of the network.
+    pub fn forward(&self, input: &[f64]) -> Vec<f64> {
+        self.layers
+            .iter()
+            .fold(input.to_vec(), |x, layer| layer.forward(&x))
+    }
+
+    /// Takes a single optimizer step towards `target` and returns the
+    /// loss before the step.
+    pub fn train(&mut self, input: &[f64], target: &[f64]) -> f64 {
+        let mut activations = vec![input.to_vec()];
+        for layer in &self.layers {
+            let next = layer.forward(activations.last().unwrap());
+            activations.push(next);
+        }
+
+        let output = activations.last().unwrap();
+        let loss = self.loss.loss(output, target);
+
+        // Gradient of the loss with respect to the sums going into each node.
+        let last = self.layers.last().unwrap().activation;
+        let mut delta: Vec<f64> = self
+            .loss
+            .gradient(output, target)
+            .iter()
+            .zip(output)
+            .map(|(g, y)| g * last.derivative(*y))
+            .collect();
+
+        self.steps += 1;
+        for i in (0..self.layers.len()).rev() {
+            let input = &activations[i];
+            let layer = &mut self.layers[i];
+
+            let mut prev = vec![0.0; layer.inputs];
+            for ((w, m), d) in layer.weights.iter_mut().zip(&mut layer.moments).zip(&delta) {
+                for (j, x) in input.iter().enumerate() {
+                    prev[j] += w[j + 1] * d;
+                    w[j + 1] -= self.optimizer.step(d * x, &mut m[j + 1], self.steps);
+                }
+                w[0] -= self.optimizer.step(*d, &mut m[0], self.steps);
+            }
+
+            if i > 0 {
+                let activation = self.layers[i - 1].activation;
+                delta = prev
+                    .iter()
+                    .zip(input)
+                    .map(|(d, x)| d * activation.derivative(*x))
+                    .collect();
+            }
+        }
+
+        loss
+    }
+
+    /// Writes the network as text, see [`Network::read`].
+    pub fn write(&self, mut out: impl Write) -> io::Result<()> {
+        let sizes: Vec<String> = self.sizes().iter().map(|s| s.to_string()).collect();
+        writeln!(out, "layers {}", sizes.join(" "))?;
+        let activations: Vec<String> = self
+            .layers
+            .iter()
+            .map(|l| l.activation.to_string())
+            .collect();
+        writeln!(out, "activations {}", activations.join(" "))?;
+        for layer in &self.layers {
+            for w in &layer.weights {
+                let w: Vec<String> = w.iter().map(|w| w.to_string()).collect();
+                writeln!(out, "{}", w.join(" "))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads a network written by [`Network::write`].
+    ///
+    /// The first line lists the size of each layer and the second the
+    /// activation of each layer past the inputs, which is `tanh` for every
+    /// layer if left out. They are followed by a line for every node past
+    /// the inputs holding its bias and then its weights.
+    pub fn read(input: impl BufRead) -> io::Result<Self> {
+        let mut lines = input.lines().peekable();
+
+        let line = lines
+            .next()
+            .unwrap_or_else(|| Err(invalid("unexpected end of network")))?;
+        let sizes = line
+            .strip_prefix("layers ")
+            .ok_or_else(|| invalid("expected layer sizes"))?
+            .split_whitespace()
+            .map(|s| s.parse().map_err(|_| invalid("invalid layer size")))
+            .collect::<io::Result<Vec<usize>>>()?;
+        if sizes.len() < 2 {
+            return Err(invalid("a network needs inputs and outputs"));
+        }
+
+        let mut activations = vec![Activation::Tanh; sizes.len() - 1];
+        if let Some(Ok(line)) = lines.peek() {
+            if let Some(names) = line.strip_prefix("activations ") {
+                activations = names
+                    .split_whitespace()
+                    .map(|s| s.parse().map_err(|err: String| invalid(&err)))
+                    .collect::<io::Result<_>>()?;
+                if activations.len() != sizes.len() - 1 {
+                    return Err(invalid("wrong number of activations"));
+                }
+                lines.next();
+            }
+        }
+
+        let mut layers = Vec::with_capacity(sizes.len() - 1);
+        for (w, activation) in sizes.windows(2).zip(activations) {
+            let mut weights = Vec::with_capacity(w[1]);
+            for _ in 0..w[1] {
+                let row = lines
+                    .next()
+                    .unwrap_or_else(|| Err(invalid("unexpected end of network")))?
+                    .split_whitespace()
+                    .map(|s| s.parse().map_err(|_| invalid("invalid weight")))
+                    .collect::<io::Result<Vec<f64>>>()?;
+                if row.len() != w[0] + 1 {
+                    return Err(invalid("wrong number of weights"));
+                }
+                weights.push(row);
+            }
+            layers.push(Layer::with_weights(w[0], activation, weights));
+        }
+
+        Ok(Self::with_layers(layers))
+    }
+}
+
+impl Model for Network {
+    fn forward(&self, input: &[f64]) -> Vec<f64> {
+        Network::forward(self, input)
+    }
+}
+
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sizes: Vec<String> = self.sizes().iter().map(|s| s.to_string()).collect();
+        write!(f, "{}", sizes.join("-"))
+    }
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
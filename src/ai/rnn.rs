@@ -0,0 +1,250 @@
+//! A recurrent (Elman) nueral network, trained with backpropagation
+//! through time.
+
+use std::fmt;
+use std::io::{self, BufRead, Write};
+
+use rand::Rng;
+
+use crate::ai::nn::Optimizer;
+
+/// Gradients are clipped to this size so they can't explode over long sequences.
+const CLIP: f64 = 5.0;
+
+/// Running averages kept by the optimizer, one for each weight.
+type Moments = Vec<Vec<(f64, f64)>>;
+
+/// A nueral network with a single hidden layer which also feeds its
+/// previous values back into itself, so its outputs can depend on every
+/// input seen so far. Every node uses `tanh`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Recurrent {
+    inputs: usize,
+    /// One row for each hidden node: its bias, then the weights of the
+    /// inputs and then the weights of the previous hidden state.
+    cell: Vec<Vec<f64>>,
+    /// One row for each output: its bias, then the weights of the hidden state.
+    output: Vec<Vec<f64>>,
+    moments: (Moments, Moments),
+    optimizer: Optimizer,
+    steps: u64,
+}
+
+impl Recurrent {
+    /// Creates a network with randomly initialised weights.
+    pub fn new(inputs: usize, hidden: usize, outputs: usize, rng: &mut impl Rng) -> Self {
+        let mut init = |rows: usize, cols: usize| -> Vec<Vec<f64>> {
+            let limit = 1.0 / (cols as f64).sqrt();
+            (0..rows)
+                .map(|_| (0..=cols).map(|_| rng.gen_range(-limit..limit)).collect())
+                .collect()
+        };
+        let cell = init(hidden, inputs + hidden);
+        let output = init(outputs, hidden);
+        Self::with_weights(inputs, cell, output)
+    }
+
+    fn with_weights(inputs: usize, cell: Vec<Vec<f64>>, output: Vec<Vec<f64>>) -> Self {
+        let zeros = |w: &Vec<Vec<f64>>| w.iter().map(|r| vec![(0.0, 0.0); r.len()]).collect();
+        Self {
+            inputs,
+            moments: (zeros(&cell), zeros(&output)),
+            cell,
+            output,
+            optimizer: Optimizer::sgd(0.01),
+            steps: 0,
+        }
+    }
+
+    pub fn optimizer(mut self, optimizer: Optimizer) -> Self {
+        self.optimizer = optimizer;
+        self
+    }
+
+    /// Returns the number of inputs, hidden nodes and outputs.
+    pub fn sizes(&self) -> (usize, usize, usize) {
+        (self.inputs, self.cell.len(), self.output.len())
+    }
+
+    /// Returns the hidden state before any input has been seen.
+    pub fn initial(&self) -> Vec<f64> {
+        vec![0.0; self.cell.len()]
+    }
+
+    /// Feeds a single input through the network, returning the next
+    /// hidden state and the outputs.
+    pub fn step(&self, state: &[f64], input: &[f64]) -> (Vec<f64>, Vec<f64>) {
+        let hidden: Vec<f64> = self
+            .cell
+            .iter()
+            .map(|w| {
+                let sum: f64 = w[1..]
+                    .iter()
+                    .zip(input.iter().chain(state))
+                    .map(|(w, x)| w * x)
+                    .sum();
+                (w[0] + sum).tanh()
+            })
+            .collect();
+        let output = self
+            .output
+            .iter()
+            .map(|w| {
+                let sum: f64 = w[1..].iter().zip(&hidden).map(|(w, h)| w * h).sum();
+                (w[0] + sum).tanh()
+            })
+            .collect();
+        (hidden, output)
+    }
+
+    /// Feeds a sequence of inputs through the network and returns the
+    /// outputs after each of them.
+    pub fn run(&self, inputs: &[Vec<f64>]) -> Vec<Vec<f64>> {
+        let mut state = self.initial();
+        inputs
+            .iter()
+            .map(|input| {
+                let (next, output) = self.step(&state, input);
+                state = next;
+                output
+            })
+            .collect()
+    }
+
+    /// Takes a single optimizer step towards a target for the outputs after
+    /// each input of the sequence, and returns the mean squared error before
+    /// the step.
+    pub fn train(&mut self, inputs: &[Vec<f64>], targets: &[Vec<f64>]) -> f64 {
+        assert_eq!(inputs.len(), targets.len(), "every input needs a target");
+
+        let mut states = vec![self.initial()];
+        let mut outputs = Vec::with_capacity(inputs.len());
+        for input in inputs {
+            let (next, output) = self.step(states.last().unwrap(), input);
+            states.push(next);
+            outputs.push(output);
+        }
+
+        let scale = (inputs.len() * self.output.len()).max(1) as f64;
+        let mut loss = 0.0;
+        let mut cell_grads: Vec<Vec<f64>> = self.cell.iter().map(|r| vec![0.0; r.len()]).collect();
+        let mut output_grads: Vec<Vec<f64>> =
+            self.output.iter().map(|r| vec![0.0; r.len()]).collect();
+
+        // Gradient of the loss with respect to the hidden state, flowing back in time.
+        let mut carried = vec![0.0; self.cell.len()];
+        for t in (0..inputs.len()).rev() {
+            let (hidden, prev) = (&states[t + 1], &states[t]);
+
+            let mut dh = carried;
+            for ((w, g), (y, target)) in self
+                .output
+                .iter()
+                .zip(&mut output_grads)
+                .zip(outputs[t].iter().zip(&targets[t]))
+            {
+                loss += (y - target).powi(2);
+                let d = 2.0 * (y - target) / scale * (1.0 - y * y);
+                g[0] += d;
+                for (j, h) in hidden.iter().enumerate() {
+                    g[j + 1] += d * h;
+                    dh[j] += d * w[j + 1];
+                }
+            }
+
+            carried = vec![0.0; self.cell.len()];
+            for (j, (w, g)) in self.cell.iter().zip(&mut cell_grads).enumerate() {
+                let d = dh[j] * (1.0 - hidden[j] * hidden[j]);
+                g[0] += d;
+                for (k, x) in inputs[t].iter().chain(prev).enumerate() {
+                    g[k + 1] += d * x;
+                }
+                for (k, c) in carried.iter_mut().enumerate() {
+                    *c += d * w[1 + self.inputs + k];
+                }
+            }
+        }
+
+        self.steps += 1;
+        let (optimizer, steps) = (self.optimizer, self.steps);
+        let update = |weights: &mut Vec<Vec<f64>>, moments: &mut Moments, grads: Vec<Vec<f64>>| {
+            for ((w, m), g) in weights.iter_mut().zip(moments).zip(grads) {
+                for ((w, m), g) in w.iter_mut().zip(m).zip(g) {
+                    *w -= optimizer.step(g.clamp(-CLIP, CLIP), m, steps);
+                }
+            }
+        };
+        update(&mut self.cell, &mut self.moments.0, cell_grads);
+        update(&mut self.output, &mut self.moments.1, output_grads);
+
+        loss / scale
+    }
+
+    /// Writes the network as text, see [`Recurrent::read`].
+    pub fn write(&self, mut out: impl Write) -> io::Result<()> {
+        let (inputs, hidden, outputs) = self.sizes();
+        writeln!(out, "recurrent {} {} {}", inputs, hidden, outputs)?;
+        for w in self.cell.iter().chain(&self.output) {
+            let w: Vec<String> = w.iter().map(|w| w.to_string()).collect();
+            writeln!(out, "{}", w.join(" "))?;
+        }
+        Ok(())
+    }
+
+    /// Reads a network written by [`Recurrent::write`].
+    ///
+    /// The first line holds the number of inputs, hidden nodes and outputs.
+    /// It is followed by a line for each hidden node holding its bias, its
+    /// input weights and its recurrent weights, then a line for each output
+    /// holding its bias and its weights.
+    pub fn read(input: impl BufRead) -> io::Result<Self> {
+        let mut lines = input.lines();
+        let mut next_line = || {
+            lines
+                .next()
+                .unwrap_or_else(|| Err(invalid("unexpected end of network")))
+        };
+
+        let line = next_line()?;
+        let sizes = line
+            .strip_prefix("recurrent ")
+            .ok_or_else(|| invalid("expected network sizes"))?
+            .split_whitespace()
+            .map(|s| s.parse().map_err(|_| invalid("invalid network size")))
+            .collect::<io::Result<Vec<usize>>>()?;
+        let (inputs, hidden, outputs) = match sizes[..] {
+            [inputs, hidden, outputs] => (inputs, hidden, outputs),
+            _ => return Err(invalid("expected inputs, hidden nodes and outputs")),
+        };
+
+        let mut rows = |count: usize, len: usize| -> io::Result<Vec<Vec<f64>>> {
+            (0..count)
+                .map(|_| {
+                    let row = next_line()?
+                        .split_whitespace()
+                        .map(|s| s.parse().map_err(|_| invalid("invalid weight")))
+                        .collect::<io::Result<Vec<f64>>>()?;
+                    if row.len() != len {
+                        return Err(invalid("wrong number of weights"));
+                    }
+                    Ok(row)
+                })
+                .collect()
+        };
+        let cell = rows(hidden, 1 + inputs + hidden)?;
+        let output = rows(outputs, 1 + hidden)?;
+
+        Ok(Self::with_weights(inputs, cell, output))
+    }
+}
+
+impl fmt::Display for Recurrent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (inputs, hidden, outputs) = self.sizes();
+        write!(f, "{}-{}r-{}", inputs, hidden, outputs)
+    }
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
@@ -0,0 +1,101 @@
+/// Describes how a stored score relates to the real score of a state.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Bound {
+    /// The score is exact.
+    Exact,
+    /// The real score is at least the stored score.
+    Lower,
+    /// The real score is at most the stored score.
+    Upper,
+}
+
+/// Result of searching a single state.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Entry<M> {
+    /// Hash of the searched state.
+    pub hash: u64,
+    /// How many moves deep the state was searched.
+    pub depth: usize,
+    pub score: i8,
+    pub bound: Bound,
+    /// Best move found, if any.
+    pub best: Option<M>,
+    /// Whether the search stopped at its depth limit somewhere below the
+    /// state, i.e. searching deeper could change the score.
+    pub cutoff: bool,
+}
+
+/// A fixed size table of states which have already been searched.
+///
+/// States are looked up by their [`ax::StateHash`](crate::StateHash). When two
+/// states compete for the same slot the newest one replaces the other, but a
+/// state searched less deep doesn't replace a deeper search of itself.
+#[derive(Debug)]
+pub struct TranspositionTable<M> {
+    entries: Vec<Option<Entry<M>>>,
+    hits: u64,
+    misses: u64,
+}
+
+impl<M> TranspositionTable<M> {
+    /// Creates a table which holds at most `capacity` entries,
+    /// rounded up to the next power of two.
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1).next_power_of_two();
+        Self {
+            entries: (0..capacity).map(|_| None).collect(),
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    fn slot(&self, hash: u64) -> usize {
+        (hash as usize) & (self.entries.len() - 1)
+    }
+
+    /// Looks up the entry for a state, counting it as a hit or miss.
+    pub fn get(&mut self, hash: u64) -> Option<&Entry<M>> {
+        let slot = self.slot(hash);
+        match &self.entries[slot] {
+            Some(entry) if entry.hash == hash => {
+                self.hits += 1;
+                self.entries[slot].as_ref()
+            }
+            _ => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Stores an entry, unless a deeper search of the same state is already stored.
+    pub fn insert(&mut self, entry: Entry<M>) {
+        let slot = self.slot(entry.hash);
+        match &self.entries[slot] {
+            Some(old) if old.hash == entry.hash && old.depth > entry.depth => {}
+            _ => self.entries[slot] = Some(entry),
+        }
+    }
+
+    /// Removes every entry and resets the hit and miss counts.
+    pub fn clear(&mut self) {
+        self.entries.iter_mut().for_each(|e| *e = None);
+        self.hits = 0;
+        self.misses = 0;
+    }
+
+    /// Returns the maximum number of entries.
+    pub fn capacity(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns how many lookups found an entry.
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    /// Returns how many lookups found nothing.
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+}
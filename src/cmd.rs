@@ -14,13 +14,28 @@ pub fn build_cli(subs: impl IntoIterator<Item = App<'static>>) -> App<'static> {
             .long("config")
             .default_value("config.yaml")
             .value_name("FILE")
-            .about("Override config file name used by ax.")
+            .about("Override config file name used by ax. Flags take precedence over it.")
+        )
+        .arg(Arg::new("seed")
+            .short('s')
+            .long("seed")
+            .value_name("SEED")
+            .about("Seed the random number generators, so a run can be repeated.")
+            .global(true)
         )
         .arg(Arg::new("verbosity")
             .short('v')
             .long("verbose")
             .multiple_occurrences(true)
-            .about("Set log verbosity.")
+            .about("Set log verbosity, repeat for more detail.")
+            .global(true)
+        )
+        .arg(Arg::new("log-format")
+            .long("log-format")
+            .value_name("FORMAT")
+            .about("Format of the log written to stderr.")
+            .possible_values(&["text", "json"])
+            .default_value("text")
             .global(true)
         )
         .subcommands(subs)
@@ -44,14 +59,59 @@ pub fn build_tic_tac_toe() -> App<'static> {
         )
         .arg(
             Arg::new("ai")
-                .about("Select AI models.")
+                .about("Select AI models: random, negamax, mcts, nn, neatnn or the path of a model file.")
                 .short('a')
                 .long("ai")
                 .multiple_occurrences(true)
                 .min_values(1)
                 .max_values(2)
-                .default_values(&["random", "random"])
-                .possible_values(&["random", "negamax", "neatnn"]),
+                .default_values(&["random", "random"]),
+        )
+        .arg(
+            Arg::new("ai-first")
+                .about("Let the AI move first in human-vs-ai.")
+                .long("ai-first")
+                .overrides_with("human-first"),
+        )
+        .arg(
+            Arg::new("human-first")
+                .about("Move first yourself in human-vs-ai, even if the config lets the AI.")
+                .long("human-first")
+                .overrides_with("ai-first"),
+        )
+        .arg(
+            Arg::new("depth")
+                .about("How many moves ahead negamax searches.")
+                .short('d')
+                .long("depth")
+                .default_value("9"),
+        )
+        .arg(
+            Arg::new("model")
+                .about("Model file used by trained AI.")
+                .short('m')
+                .long("model")
+                .value_name("FILE")
+                .default_value("tic-tac-toe.model"),
+        )
+        .arg(
+            Arg::new("record")
+                .about("Write a transcript of the game to a file.")
+                .long("record")
+                .value_name("FILE"),
+        )
+        .arg(
+            Arg::new("resume")
+                .about("Resume a game saved by entering save instead of a position.")
+                .long("resume")
+                .value_name("FILE"),
+        )
+        .arg(
+            Arg::new("style")
+                .about("How to show the game.")
+                .long("style")
+                .possible_values(&["plain", "ansi", "compact", "json"])
+                .default_value("plain"),
         )
 }
 
@@ -74,9 +134,33 @@ pub fn build_number_guesser() -> App<'static> {
             .default_value("100")
         )
         .arg(Arg::new("ai")
-            .about("Use AI to play for you.")
+            .about("Use AI to play for you: binary-search, random or a model trained by `ax train`.")
             .short('a')
             .long("with-ai")
+            .value_name("STRATEGY")
+            .possible_values(&["binary-search", "random", "model"])
+        )
+        .arg(Arg::new("model")
+            .about("Model file used by the model strategy, number-guesser.model by default.")
+            .short('m')
+            .long("model")
+            .value_name("FILE")
+        )
+        .arg(Arg::new("record")
+            .about("Write a transcript of the game to a file.")
+            .long("record")
+            .value_name("FILE")
+        )
+        .arg(Arg::new("resume")
+            .about("Resume a game saved by entering save instead of a guess.")
+            .long("resume")
+            .value_name("FILE")
+        )
+        .arg(Arg::new("style")
+            .about("How to show the game.")
+            .long("style")
+            .possible_values(&["plain", "ansi", "compact", "json"])
+            .default_value("plain")
         )
 }
 
@@ -88,6 +172,64 @@ pub fn build_train(
         .setting(AppSettings::SubcommandRequiredElseHelp)
         .subcommands(ais.into_iter().map(|(ai, values)| {
             ai.setting(AppSettings::SubcommandRequiredElseHelp)
+                .arg(
+                    Arg::new("epochs")
+                        .short('e')
+                        .long("epochs")
+                        .about("Number of epochs (or generations) to train for.")
+                        .default_value("100"),
+                )
+                .arg(
+                    Arg::new("games")
+                        .short('g')
+                        .long("games")
+                        .about("Number of games played each epoch.")
+                        .default_value("100"),
+                )
+                .arg(
+                    Arg::new("rate")
+                        .short('r')
+                        .long("learning-rate")
+                        .about("Learning rate used by backprop.")
+                        .default_value("0.01")
+                        .validator(at_least_zero),
+                )
+                .arg(
+                    Arg::new("optimizer")
+                        .long("optimizer")
+                        .about("Optimizer used by backprop.")
+                        .possible_values(&["sgd", "adam"])
+                        .default_value("sgd"),
+                )
+                .arg(
+                    Arg::new("loss")
+                        .long("loss")
+                        .about("Loss minimised by backprop.")
+                        .possible_values(&["mse", "cross-entropy"])
+                        .default_value("mse"),
+                )
+                .arg(
+                    Arg::new("activation")
+                        .long("activation")
+                        .about("Activation of the hidden layers.")
+                        .possible_values(&["linear", "tanh", "sigmoid", "relu"])
+                        .default_value("tanh"),
+                )
+                .arg(
+                    Arg::new("population")
+                        .short('p')
+                        .long("population")
+                        .about("Number of genomes in each generation of neat.")
+                        .default_value("150")
+                        .validator(at_least_one),
+                )
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .value_name("FILE")
+                        .about("Where to save the trained model, <game>.model by default."),
+                )
                 .subcommand(
                     App::new("for")
                         .about("Select which game to train for.")
@@ -101,6 +243,24 @@ pub fn build_train(
         }))
 }
 
+/// Accepts counts of `1` or more.
+fn at_least_one(value: &str) -> Result<usize, String> {
+    match value.parse() {
+        Ok(0) => Err("must be at least 1".to_string()),
+        Ok(n) => Ok(n),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+/// Accepts numbers of `0` or more, but not NaN or infinity.
+fn at_least_zero(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(n) if n.is_finite() && n >= 0.0 => Ok(n),
+        Ok(_) => Err("must be a number of at least 0".to_string()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
 pub fn build_nn() -> (App<'static>, &'static [&'static str]) {
     (
         App::new("nueral-network")
@@ -119,7 +279,15 @@ pub fn build_nn() -> (App<'static>, &'static [&'static str]) {
                     .short('l')
                     .long("layer")
                     .about("Provide (starting) count of nodes in \"hidden\" layers.")
-                    .multiple_occurrences(true),
+                    .takes_value(true)
+                    .multiple_occurrences(true)
+                    .validator(at_least_one),
+            )
+            .arg(
+                Arg::new("from")
+                    .long("from")
+                    .value_name("FILE")
+                    .about("Learn from games recorded with --record instead of self-play."),
             ),
         &["tic-tac-toe", "number-guesser"],
     )
@@ -143,8 +311,222 @@ pub fn build_rnn() -> (App<'static>, &'static [&'static str]) {
                     .short('l')
                     .long("layer")
                     .about("Provide (starting) count of nodes in \"hidden\" layers.")
-                    .multiple_occurrences(true),
+                    .takes_value(true)
+                    .multiple_occurrences(true)
+                    .validator(at_least_one),
+            )
+            .arg(
+                Arg::new("from")
+                    .long("from")
+                    .value_name("FILE")
+                    .about("Learn from games recorded with --record instead of self-play."),
             ),
         &["tic-tac-toe", "number-guesser"],
     )
 }
+
+pub fn build_config() -> App<'static> {
+    App::new("config")
+        .about("Manage the config file.")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(
+            App::new("init")
+                .about("Write a commented config file holding every default.")
+                .arg(
+                    Arg::new("force")
+                        .short('f')
+                        .long("force")
+                        .about("Overwrite the config file if it already exists."),
+                ),
+        )
+}
+
+pub fn build_tournament() -> App<'static> {
+    App::new("tournament")
+        .about("Play AI against each other at tic-tac-toe and compare the results.")
+        .arg(
+            Arg::new("ai")
+                .about(
+                    "AI to enter: random, negamax, mcts, nn, neatnn or the path of a model file.",
+                )
+                .short('a')
+                .long("ai")
+                .multiple_occurrences(true)
+                .min_values(2)
+                .required(true),
+        )
+        .arg(
+            Arg::new("games")
+                .about("Number of games played by each pair of AI.")
+                .short('g')
+                .long("games")
+                .default_value("100"),
+        )
+        .arg(
+            Arg::new("threads")
+                .about("Number of games played at the same time, one per CPU by default.")
+                .short('j')
+                .long("threads")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("depth")
+                .about("How many moves ahead negamax searches.")
+                .short('d')
+                .long("depth")
+                .default_value("9"),
+        )
+        .arg(
+            Arg::new("model")
+                .about("Model file used by trained AI.")
+                .short('m')
+                .long("model")
+                .value_name("FILE")
+                .default_value("tic-tac-toe.model"),
+        )
+        .arg(
+            Arg::new("format")
+                .about("How to write the results.")
+                .short('f')
+                .long("format")
+                .possible_values(&["table", "csv", "json"])
+                .default_value("table"),
+        )
+        .arg(
+            Arg::new("ratings")
+                .about("Ledger holding the ratings updated by the results.")
+                .short('r')
+                .long("ratings")
+                .value_name("FILE")
+                .default_value("ratings.txt"),
+        )
+        .arg(
+            Arg::new("unrated")
+                .about("Don't update any ratings.")
+                .long("unrated"),
+        )
+}
+
+pub fn build_sprt() -> App<'static> {
+    App::new("sprt")
+        .about("Test whether a candidate AI is stronger than a baseline at tic-tac-toe.")
+        .arg(
+            Arg::new("baseline")
+                .about("AI to compare against: random, negamax, mcts, nn, neatnn or the path of a model file.")
+                .short('b')
+                .long("baseline")
+                .value_name("AI")
+                .required(true),
+        )
+        .arg(
+            Arg::new("candidate")
+                .about("AI being tested, named like the baseline.")
+                .short('c')
+                .long("candidate")
+                .value_name("AI")
+                .required(true),
+        )
+        .arg(
+            Arg::new("elo0")
+                .about("Elo difference of the null hypothesis.")
+                .long("elo0")
+                .allow_hyphen_values(true)
+                .default_value("0"),
+        )
+        .arg(
+            Arg::new("elo1")
+                .about("Elo difference of the alternative hypothesis.")
+                .long("elo1")
+                .allow_hyphen_values(true)
+                .default_value("50"),
+        )
+        .arg(
+            Arg::new("alpha")
+                .about("Chance of accepting the alternative hypothesis when it is false.")
+                .long("alpha")
+                .default_value("0.05"),
+        )
+        .arg(
+            Arg::new("beta")
+                .about("Chance of accepting the null hypothesis when it is false.")
+                .long("beta")
+                .default_value("0.05"),
+        )
+        .arg(
+            Arg::new("max-games")
+                .about("Give up without a verdict after this many games.")
+                .short('g')
+                .long("max-games")
+                .default_value("10000"),
+        )
+        .arg(
+            Arg::new("depth")
+                .about("How many moves ahead negamax searches.")
+                .short('d')
+                .long("depth")
+                .default_value("9"),
+        )
+        .arg(
+            Arg::new("model")
+                .about("Model file used by trained AI.")
+                .short('m')
+                .long("model")
+                .value_name("FILE")
+                .default_value("tic-tac-toe.model"),
+        )
+}
+
+pub fn build_replay() -> App<'static> {
+    App::new("replay")
+        .about("Step through a game recorded with --record.")
+        .arg(
+            Arg::new("file")
+                .about("Transcript of the game.")
+                .value_name("FILE")
+                .required(true),
+        )
+        .arg(
+            Arg::new("play")
+                .about("Play the whole game instead of waiting for commands.")
+                .long("play"),
+        )
+        .arg(
+            Arg::new("delay")
+                .about("Milliseconds to wait between moves when playing.")
+                .long("delay")
+                .value_name("MS")
+                .default_value("1000"),
+        )
+        .arg(
+            Arg::new("depth")
+                .about("How many moves ahead negamax searches to evaluate each position of tic-tac-toe.")
+                .short('d')
+                .long("depth")
+                .default_value("9"),
+        )
+        .arg(
+            Arg::new("no-eval")
+                .about("Don't evaluate positions.")
+                .long("no-eval"),
+        )
+        .arg(
+            Arg::new("style")
+                .about("How to show the game.")
+                .long("style")
+                .possible_values(&["plain", "ansi", "compact", "json"])
+                .default_value("plain"),
+        )
+}
+
+pub fn build_ratings() -> App<'static> {
+    App::new("ratings")
+        .about("Show the Elo and Glicko-2 ratings earned in tournaments.")
+        .arg(
+            Arg::new("ratings")
+                .about("Ledger holding the ratings.")
+                .short('r')
+                .long("ratings")
+                .value_name("FILE")
+                .default_value("ratings.txt"),
+        )
+}
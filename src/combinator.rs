@@ -1,6 +1,14 @@
+use std::fmt::{self, Display};
 use std::io;
+use std::str::FromStr;
+use std::time::{Instant, SystemTime};
 
-use crate::{Action, AsBytes, Player, State, Status};
+use tracing::{debug, info, trace};
+
+use crate::render::{Render, Style};
+use crate::snapshot::{Fields, Position};
+use crate::transcript::{self, Transcript, Turn};
+use crate::{Action, Game, Player, PlayerError, State, Status};
 
 /// Map over the result of an action.
 pub fn map<AE, SE, S1, S2, S3, A>(
@@ -27,7 +35,7 @@ where
     A: Action<AE1, SE, S1, S2>,
     F: Fn(AE1) -> AE2,
 {
-    move |state: S1| action.apply(state).map_err(|e| f(e))
+    move |state: S1| action.apply(state).map_err(&f)
 }
 
 /// Map one action over the result of another.
@@ -83,16 +91,23 @@ where
     S: State<SE>,
     A: Action<AE, SE, S, S>,
 {
-    move |mut state: S| loop {
-        state = action.apply(state)?;
-        match state.status() {
-            Ok(s) => {
-                if s == Status::Terminal {
-                    break Ok(state);
+    move |mut state: S| {
+        let mut previous = None;
+        for step in 1.. {
+            trace!(step, "step");
+            state = action.apply(state)?;
+            if let Ok(status) = state.status() {
+                if previous.as_ref() != Some(&status) {
+                    debug!(step, status = ?status, "status changed");
                 }
+                if let Status::Terminal(outcome) = &status {
+                    info!(steps = step, outcome = ?outcome, "game over");
+                    break;
+                }
+                previous = Some(status);
             }
-            Err(_) => {}
         }
+        Ok(state)
     }
 }
 
@@ -126,17 +141,24 @@ impl<E, S: State<E>> State<E> for (usize, S) {
     fn status(&self) -> Result<Status, E> {
         self.1.status()
     }
+
+    fn current_player(&self) -> usize {
+        self.1.current_player()
+    }
 }
 
-impl<S> AsBytes for (usize, S)
+impl<S> Render for (usize, S)
 where
-    S: AsBytes,
+    S: Render,
 {
-    fn as_bytes(&self) -> Vec<u8> {
-        self.1.as_bytes()
+    fn render(&self, style: Style, out: &mut dyn fmt::Write) -> fmt::Result {
+        self.1.render(style, out)
     }
 }
 
+/// The result of one of two actions, see [`if_then_else`].
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Either<L, R> {
     Left(L),
     Right(R),
@@ -153,17 +175,24 @@ where
             Either::Right(s) => s.status(),
         }
     }
+
+    fn current_player(&self) -> usize {
+        match self {
+            Either::Left(s) => s.current_player(),
+            Either::Right(s) => s.current_player(),
+        }
+    }
 }
 
-impl<L, R> AsBytes for Either<L, R>
+impl<L, R> Render for Either<L, R>
 where
-    L: AsBytes,
-    R: AsBytes,
+    L: Render,
+    R: Render,
 {
-    fn as_bytes(&self) -> Vec<u8> {
+    fn render(&self, style: Style, out: &mut dyn fmt::Write) -> fmt::Result {
         match self {
-            Either::Left(s) => s.as_bytes(),
-            Either::Right(s) => s.as_bytes(),
+            Either::Left(s) => s.render(style, out),
+            Either::Right(s) => s.render(style, out),
         }
     }
 }
@@ -194,21 +223,168 @@ where
     }
 }
 
-/// Render the game state.
-pub fn render<E, S>(mut out: impl io::Write) -> impl FnMut(S) -> Result<S, io::Error>
+/// Render the game state in the given style.
+pub fn render<E, S>(mut out: impl io::Write, style: Style) -> impl FnMut(S) -> Result<S, io::Error>
+where
+    S: State<E> + Render,
+{
+    move |state: S| {
+        state.render_io(style, &mut out)?;
+        Ok(state)
+    }
+}
+
+/// Record the moves made by an action in a transcript.
+///
+/// Each move is written to `out` as soon as it is made, along with who
+/// made it and how long they took, so the transcript of a game which was
+/// cut short is still readable. See [`transcript`] for the format.
+pub fn record<AE, SE, S, A>(
+    mut out: impl io::Write,
+    mut action: A,
+) -> impl FnMut(S) -> Result<S, AE>
 where
-    S: State<E> + AsBytes,
+    S: Game<SE> + Position,
+    S::Move: Display,
+    A: Action<AE, SE, S, S>,
+    AE: From<io::Error>,
 {
+    let mut started = false;
     move |state: S| {
-        out.write(&state.as_bytes())?;
+        if !started {
+            transcript::write_header(&mut out, S::GAME, &Fields::of(&state))?;
+            started = true;
+        }
+
+        let seat = state.current_player();
+        let moves = Position::moves(&state);
+        let time = SystemTime::now();
+        let clock = Instant::now();
+        let state = action.apply(state)?;
+        let spent = clock.elapsed();
+
+        match Position::moves(&state).checked_sub(moves) {
+            Some(0) => {}
+            Some(1) => {
+                let mv = state
+                    .last_move()
+                    .expect("a game which made a move should have a last move");
+                writeln!(
+                    out,
+                    "{}",
+                    Turn {
+                        seat,
+                        time,
+                        spent,
+                        mv
+                    }
+                )?;
+            }
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "a turn did something other than make a single move",
+                )
+                .into())
+            }
+        }
+        if let Ok(Status::Terminal(outcome)) = state.status() {
+            transcript::write_end(&mut out, &outcome)?;
+            started = false;
+        }
+        out.flush()?;
         Ok(state)
     }
 }
 
+/// Replay the next game of a transcript, checking each move is made by the
+/// player whose turn it is and is legal, and that the game ends the same way.
+///
+/// The game is set up from the position the transcript starts from, with
+/// the state given as a new game giving anything the transcript leaves out.
+pub fn replay<SE, S>(mut input: impl io::BufRead) -> impl FnMut(S) -> Result<S, io::Error>
+where
+    S: Game<SE> + Position,
+    S::Move: Display + FromStr,
+{
+    move |new: S| Transcript::read(&mut input)?.replay(new)
+}
+
 /// Take a players turn.
-pub fn take_turn<SE, S>(mut player: impl Player<S>) -> impl FnMut(S) -> Result<S, ()>
+pub fn take_turn<SE, S>(mut player: impl Player<S>) -> impl FnMut(S) -> Result<S, PlayerError<S>>
+where
+    S: State<SE>,
+{
+    move |state: S| player.take_turn(state)
+}
+
+/// Let whoever's turn it is take their turn.
+///
+/// `players` are indexed by seat, so the player at
+/// [`State::current_player`] is the one asked to move.
+pub fn turns<SE, S, P>(mut players: Vec<P>) -> impl FnMut(S) -> Result<S, PlayerError<S>>
 where
     S: State<SE>,
+    P: Player<S>,
 {
-    move |state: S| Ok(player.take_turn(state))
+    move |state: S| {
+        let seat = state.current_player();
+        let player = players
+            .get_mut(seat)
+            .unwrap_or_else(|| panic!("no player in seat {}", seat));
+        let started = Instant::now();
+        let res = player.take_turn(state);
+        if res.is_ok() {
+            debug!(seat, elapsed = ?started.elapsed(), "turn taken");
+        }
+        res
+    }
+}
+
+/// How many times [`retry`] tries an action again before giving up.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Retry {
+    /// Keep trying until the action succeeds or fails for good.
+    Forever,
+    /// Give up after retrying the given number of times, so the action is
+    /// tried once more than that. `Times(0)` tries it just once.
+    Times(usize),
+}
+
+impl Retry {
+    fn allows(self, tries: usize) -> bool {
+        match self {
+            Retry::Forever => true,
+            Retry::Times(n) => tries <= n,
+        }
+    }
+}
+
+/// Retry an action whenever it fails with a recoverable [`PlayerError`].
+///
+/// `report` is given the reason of each failed try before trying again,
+/// e.g. to let a human know what was wrong with their input.
+pub fn retry<SE, S, A>(
+    mut action: A,
+    policy: Retry,
+    mut report: impl FnMut(&str),
+) -> impl FnMut(S) -> Result<S, PlayerError<S>>
+where
+    S: State<SE>,
+    A: Action<PlayerError<S>, SE, S, S>,
+{
+    move |mut state: S| {
+        let mut tries = 1;
+        loop {
+            match action.apply(state) {
+                Err(PlayerError::Invalid(s, reason)) if policy.allows(tries) => {
+                    debug!(tries, reason = %reason, "turn rejected");
+                    report(&reason);
+                    tries += 1;
+                    state = s;
+                }
+                res => break res,
+            }
+        }
+    }
 }
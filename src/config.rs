@@ -0,0 +1,440 @@
+//! Settings read from the config file, `config.yaml` by default.
+//!
+//! The file is a small subset of YAML: sections holding `key: value` pairs,
+//! indented below the section's name, and a few top level keys. Lists are
+//! written inline, e.g. `[random, mcts]`, and `#` starts a comment.
+//!
+//! ```yaml
+//! seed: 42
+//! negamax:
+//!   depth: 9
+//! train:
+//!   layers: [18, 9]
+//! ```
+//!
+//! Every key is optional, anything left out keeps its default. Anything
+//! else YAML allows, like block lists or nested sections, is rejected
+//! rather than misread.
+
+use std::error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::ai::nn::{Activation, Loss};
+
+/// A commented config file holding every default, written by `ax config init`.
+pub const DEFAULT: &str = "\
+# Config file for ax. Flags given on the command line take precedence.
+
+# Seed for the random number generators, leave it out to pick one at random.
+# seed: 42
+
+number-guesser:
+  # The number to guess is picked from low up to, but not including, high.
+  low: 0
+  high: 100
+
+tic-tac-toe:
+  # One of human-vs-ai, human-vs-human or ai-vs-ai.
+  mode: human-vs-ai
+  # random, negamax, mcts, nn, neatnn or the path of a model file.
+  ai: [random, random]
+  # Let the AI move first in human-vs-ai.
+  ai-first: false
+  # Model file used by nn and neatnn.
+  model: tic-tac-toe.model
+
+negamax:
+  # How many moves ahead to search.
+  depth: 9
+
+mcts:
+  # Playouts run for each move.
+  iterations: 10000
+  # How eagerly rarely visited moves are explored.
+  exploration: 1.4142135623730951
+
+train:
+  epochs: 100
+  games: 100
+  learning-rate: 0.01
+  # sgd or adam.
+  optimizer: sgd
+  # mse or cross-entropy.
+  loss: mse
+  # linear, tanh, sigmoid or relu.
+  activation: tanh
+  # Number of genomes in each generation of neat.
+  population: 150
+  # Nodes in each hidden layer, each trainer picks its own if empty.
+  layers: []
+";
+
+const SECTIONS: &[&str] = &["number-guesser", "tic-tac-toe", "negamax", "mcts", "train"];
+const MODES: &[&str] = &["human-vs-ai", "human-vs-human", "ai-vs-ai"];
+const OPTIMIZERS: &[&str] = &["sgd", "adam"];
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Config {
+    pub seed: Option<u64>,
+    pub number_guesser: NumberGuesser,
+    pub tic_tac_toe: TicTacToe,
+    pub negamax: Negamax,
+    pub mcts: Mcts,
+    pub train: Train,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct NumberGuesser {
+    pub low: i64,
+    pub high: i64,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct TicTacToe {
+    pub mode: String,
+    pub ai: Vec<String>,
+    pub ai_first: bool,
+    pub model: String,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Negamax {
+    pub depth: usize,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Mcts {
+    pub iterations: u64,
+    pub exploration: f64,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Train {
+    pub epochs: usize,
+    pub games: usize,
+    pub learning_rate: f64,
+    pub optimizer: String,
+    pub loss: Loss,
+    pub activation: Activation,
+    pub population: usize,
+    pub layers: Vec<usize>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            seed: None,
+            number_guesser: NumberGuesser { low: 0, high: 100 },
+            tic_tac_toe: TicTacToe {
+                mode: "human-vs-ai".to_string(),
+                ai: vec!["random".to_string(), "random".to_string()],
+                ai_first: false,
+                model: "tic-tac-toe.model".to_string(),
+            },
+            negamax: Negamax { depth: 9 },
+            mcts: Mcts {
+                iterations: 10_000,
+                exploration: std::f64::consts::SQRT_2,
+            },
+            train: Train {
+                epochs: 100,
+                games: 100,
+                learning_rate: 0.01,
+                optimizer: "sgd".to_string(),
+                loss: Loss::MeanSquared,
+                activation: Activation::Tanh,
+                population: 150,
+                layers: Vec::new(),
+            },
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    /// A line which isn't a section or a `key: value` pair.
+    Syntax {
+        line: usize,
+        msg: String,
+    },
+    UnknownKey {
+        line: usize,
+        key: String,
+    },
+    UnknownSection {
+        line: usize,
+        section: String,
+    },
+    Invalid {
+        line: usize,
+        key: String,
+        value: String,
+        reason: String,
+    },
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "{}", err),
+            Error::Syntax { line, msg } => write!(f, "line {}: {}", line, msg),
+            Error::UnknownKey { line, key } => write!(f, "line {}: unknown key {:?}", line, key),
+            Error::UnknownSection { line, section } => write!(
+                f,
+                "line {}: unknown section {:?}, expected one of {}",
+                line,
+                section,
+                SECTIONS.join(", ")
+            ),
+            Error::Invalid {
+                line,
+                key,
+                value,
+                reason,
+            } => write!(
+                f,
+                "line {}: invalid value {:?} for {}: {}",
+                line, value, key, reason
+            ),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+impl Config {
+    /// Reads the config file at `path`. A missing file gives the defaults
+    /// unless `required` is set.
+    pub fn load(path: &Path, required: bool) -> Result<Self, Error> {
+        match fs::read_to_string(path) {
+            Ok(text) => text.parse(),
+            Err(err) if err.kind() == io::ErrorKind::NotFound && !required => Ok(Self::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn set(&mut self, line: usize, key: &str, value: &str) -> Result<(), Error> {
+        let invalid = |reason: String| Error::Invalid {
+            line,
+            key: key.to_string(),
+            value: value.to_string(),
+            reason,
+        };
+        let one_of = |choices: &[&str]| -> Result<String, Error> {
+            if choices.contains(&value) {
+                Ok(value.to_string())
+            } else {
+                Err(invalid(format!("expected one of {}", choices.join(", "))))
+            }
+        };
+
+        match key {
+            "seed" => self.seed = Some(parse(value).map_err(invalid)?),
+            "number-guesser.low" => self.number_guesser.low = parse(value).map_err(invalid)?,
+            "number-guesser.high" => self.number_guesser.high = parse(value).map_err(invalid)?,
+            "tic-tac-toe.mode" => self.tic_tac_toe.mode = one_of(MODES)?,
+            "tic-tac-toe.ai" => {
+                let ai: Vec<String> = list(value).map_err(invalid)?;
+                if ai.is_empty() || ai.len() > 2 {
+                    return Err(invalid("expected one or two AI".to_string()));
+                }
+                self.tic_tac_toe.ai = ai;
+            }
+            "tic-tac-toe.ai-first" => self.tic_tac_toe.ai_first = parse(value).map_err(invalid)?,
+            "tic-tac-toe.model" => self.tic_tac_toe.model = value.to_string(),
+            "negamax.depth" => self.negamax.depth = parse(value).map_err(invalid)?,
+            "mcts.iterations" => {
+                let iterations = parse(value).map_err(invalid)?;
+                if iterations == 0 {
+                    return Err(invalid("must be at least 1".to_string()));
+                }
+                self.mcts.iterations = iterations;
+            }
+            "mcts.exploration" => {
+                let exploration: f64 = parse(value).map_err(invalid)?;
+                if !exploration.is_finite() || exploration < 0.0 {
+                    return Err(invalid("must be a number of at least 0".to_string()));
+                }
+                self.mcts.exploration = exploration;
+            }
+            "train.epochs" => self.train.epochs = parse(value).map_err(invalid)?,
+            "train.games" => self.train.games = parse(value).map_err(invalid)?,
+            "train.learning-rate" => {
+                let rate: f64 = parse(value).map_err(invalid)?;
+                if !rate.is_finite() || rate < 0.0 {
+                    return Err(invalid("must be a number of at least 0".to_string()));
+                }
+                self.train.learning_rate = rate;
+            }
+            "train.optimizer" => self.train.optimizer = one_of(OPTIMIZERS)?,
+            "train.loss" => self.train.loss = parse(value).map_err(invalid)?,
+            "train.activation" => self.train.activation = parse(value).map_err(invalid)?,
+            "train.population" => {
+                let population = parse(value).map_err(invalid)?;
+                if population == 0 {
+                    return Err(invalid("must be at least 1".to_string()));
+                }
+                self.train.population = population;
+            }
+            "train.layers" => {
+                let layers: Vec<usize> = list(value).map_err(invalid)?;
+                if layers.contains(&0) {
+                    return Err(invalid("every layer needs at least 1 node".to_string()));
+                }
+                self.train.layers = layers;
+            }
+            _ => {
+                return Err(Error::UnknownKey {
+                    line,
+                    key: key.to_string(),
+                })
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Config {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self, Error> {
+        let mut config = Self::default();
+        let mut section: Option<String> = None;
+        let mut high_line = 0;
+
+        for (i, line) in text.lines().enumerate() {
+            let number = i + 1;
+            let line = strip_comment(line);
+            if line.trim().is_empty() {
+                continue;
+            }
+            let syntax = |msg: String| Error::Syntax { line: number, msg };
+            if line.starts_with('\t') {
+                return Err(syntax("indent with spaces, not tabs".to_string()));
+            }
+            if line.trim().starts_with('-') {
+                return Err(syntax(
+                    "block lists aren't supported, write lists inline such as [a, b]".to_string(),
+                ));
+            }
+            let indented = line.starts_with(' ');
+            let (key, value) = match line.trim().split_once(':') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => {
+                    return Err(syntax(format!(
+                        "expected \"key: value\", found {:?}",
+                        line.trim()
+                    )))
+                }
+            };
+            if value.starts_with(&['{', '|', '>', '&', '*', '!'][..]) {
+                return Err(syntax(format!("unsupported value {:?} for {}", value, key)));
+            }
+
+            if value.is_empty() && (indented || key == "seed") {
+                return Err(syntax(format!(
+                    "{} needs a value, nested sections and block lists aren't supported",
+                    key
+                )));
+            }
+
+            let key = match (indented, &section) {
+                (false, _) if value.is_empty() => {
+                    if !SECTIONS.contains(&key) {
+                        return Err(Error::UnknownSection {
+                            line: number,
+                            section: key.to_string(),
+                        });
+                    }
+                    section = Some(key.to_string());
+                    continue;
+                }
+                (false, _) => {
+                    section = None;
+                    key.to_string()
+                }
+                (true, Some(section)) => format!("{}.{}", section, key),
+                (true, None) => {
+                    return Err(syntax(format!(
+                        "{:?} is indented but isn't in a section",
+                        key
+                    )))
+                }
+            };
+            if key == "number-guesser.high" {
+                high_line = number;
+            }
+            config.set(number, &key, unquote(value))?;
+        }
+
+        let NumberGuesser { low, high } = config.number_guesser;
+        if low >= high {
+            return Err(Error::Invalid {
+                line: high_line,
+                key: "number-guesser.high".to_string(),
+                value: high.to_string(),
+                reason: format!("must be greater than low ({})", low),
+            });
+        }
+        Ok(config)
+    }
+}
+
+/// Removes a trailing comment, a `#` at the start of the line or after whitespace.
+fn strip_comment(line: &str) -> &str {
+    let mut prev = ' ';
+    for (i, c) in line.char_indices() {
+        if c == '#' && prev.is_whitespace() {
+            return &line[..i];
+        }
+        prev = c;
+    }
+    line
+}
+
+fn unquote(value: &str) -> &str {
+    for quote in &['"', '\''] {
+        if let Some(inner) = value
+            .strip_prefix(*quote)
+            .and_then(|v| v.strip_suffix(*quote))
+        {
+            return inner;
+        }
+    }
+    value
+}
+
+fn parse<T: FromStr>(value: &str) -> Result<T, String>
+where
+    T::Err: fmt::Display,
+{
+    value.parse().map_err(|err: T::Err| err.to_string())
+}
+
+/// Parses an inline list such as `[18, 9]`.
+fn list<T: FromStr>(value: &str) -> Result<Vec<T>, String>
+where
+    T::Err: fmt::Display,
+{
+    let inner = value
+        .strip_prefix('[')
+        .and_then(|v| v.strip_suffix(']'))
+        .ok_or_else(|| "expected a list such as [a, b]".to_string())?;
+    inner
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(|item| parse(unquote(item)))
+        .collect()
+}
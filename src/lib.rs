@@ -1,18 +1,95 @@
 //! A "functional" game engine.
 
+use std::error;
+use std::fmt;
+use std::io;
+
 pub mod ai;
 pub mod combinator;
+pub mod config;
+pub mod numberguesser;
+pub mod players;
+pub mod ratings;
+pub mod render;
+pub mod seed;
+pub mod snapshot;
+pub mod sprt;
+pub mod tictactoe;
+pub mod tournament;
+pub mod train;
+pub mod transcript;
+pub mod viewer;
 
 /// Status
-#[derive(Debug, PartialEq, Copy, Clone)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Status {
     Valid,
-    Terminal,
+    Terminal(Outcome),
+}
+
+impl Status {
+    /// Returns `true` if the game is over.
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, Status::Terminal(_))
+    }
+
+    /// Returns the outcome of the game, if it is over.
+    pub fn outcome(&self) -> Option<&Outcome> {
+        match self {
+            Status::Valid => None,
+            Status::Terminal(outcome) => Some(outcome),
+        }
+    }
+}
+
+/// Outcome of a finished game.
+///
+/// Players are identified by their seat, i.e. `0` is the player
+/// who moved first, `1` the player who moved second and so on.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Outcome {
+    /// A single player won the game.
+    Winner(usize),
+    /// The game ended without a winner.
+    Draw,
+    /// The game ended with a score for each player, indexed by seat.
+    Scores(Vec<i64>),
+}
+
+impl Outcome {
+    /// Returns the seat of the winning player, if there is exactly one.
+    ///
+    /// For [`Outcome::Scores`] this is the player with the highest score,
+    /// unless that score is shared.
+    pub fn winner(&self) -> Option<usize> {
+        match self {
+            Outcome::Winner(p) => Some(*p),
+            Outcome::Draw => None,
+            Outcome::Scores(scores) => {
+                let max = scores.iter().max()?;
+                let mut best = scores.iter().enumerate().filter(|(_, s)| *s == max);
+                match (best.next(), best.next()) {
+                    (Some((p, _)), None) => Some(p),
+                    _ => None,
+                }
+            }
+        }
+    }
 }
 
 /// State
 pub trait State<E> {
     fn status(&self) -> Result<Status, E>;
+
+    /// Returns the seat of the player whose turn it is, using the same
+    /// numbering as [`Outcome`].
+    ///
+    /// Games with a single player can rely on the default of always `0`.
+    fn current_player(&self) -> usize {
+        0
+    }
 }
 
 /// Action
@@ -76,42 +153,129 @@ pub trait FiniteState<E>: State<E> {
         Self: Sized;
 }
 
+/// Game
+///
+/// A state which is advanced by making moves on it in place, instead of
+/// building every possible next state up front like [`FiniteState`] does.
+/// Any `Game` which can be cloned is also a `FiniteState`.
+pub trait Game<E>: State<E> {
+    /// A single move a player can make.
+    type Move: Clone + PartialEq;
+
+    /// Returns every move the player whose turn it is can make.
+    ///
+    /// This is empty once the game is over.
+    fn legal_moves(&self) -> Vec<Self::Move>;
+
+    /// Makes a move, which must be one of [`Game::legal_moves`].
+    fn apply(&mut self, mv: Self::Move);
+
+    /// Takes back the last move made with [`Game::apply`].
+    fn undo(&mut self, mv: Self::Move);
+
+    /// Returns the last move made with [`Game::apply`], if any.
+    fn last_move(&self) -> Option<Self::Move>;
+
+    /// Checks if a move can be made.
+    fn is_legal(&self, mv: &Self::Move) -> bool {
+        self.legal_moves().contains(mv)
+    }
+}
+
+impl<E, G> FiniteState<E> for G
+where
+    G: Game<E> + Clone,
+{
+    fn next_possibilities(&self) -> Option<Vec<Self>> {
+        if let Ok(status) = self.status() {
+            if status.is_terminal() {
+                return None;
+            }
+        }
+
+        Some(
+            self.legal_moves()
+                .into_iter()
+                .map(|mv| {
+                    let mut next = self.clone();
+                    next.apply(mv);
+                    next
+                })
+                .collect(),
+        )
+    }
+}
+
+/// StateHash
+///
+/// A cheap hash of a state, e.g. one which is kept up to date as moves are
+/// made. States which are equal must hash the same, no matter which moves
+/// led to them, so search AIs can recognise positions they have seen before.
+pub trait StateHash {
+    fn state_hash(&self) -> u64;
+}
+
 /// Player
 pub trait Player<State> {
-    fn take_turn(&mut self, state: State) -> State;
+    fn take_turn(&mut self, state: State) -> Result<State, PlayerError<State>>;
+}
+
+impl<S, P> Player<S> for Box<P>
+where
+    P: Player<S> + ?Sized,
+{
+    fn take_turn(&mut self, state: S) -> Result<S, PlayerError<S>> {
+        (**self).take_turn(state)
+    }
 }
 
-/// Helper trait for types that can be viewed as a byte slice
-pub trait AsBytes {
-    /// Casts the input type to a byte slice
-    fn as_bytes(&self) -> Vec<u8>;
+impl<S, P> Player<S> for &mut P
+where
+    P: Player<S> + ?Sized,
+{
+    fn take_turn(&mut self, state: S) -> Result<S, PlayerError<S>> {
+        (**self).take_turn(state)
+    }
+}
+
+/// PlayerError is returned by players who fail to take their turn.
+#[derive(Debug)]
+pub enum PlayerError<S> {
+    /// The player tried to do something which isn't allowed, like entering
+    /// gibberish or making an illegal move. The state is handed back untouched
+    /// so the player can be asked again.
+    Invalid(S, String),
+    /// The player could not take their turn at all, e.g. their input was closed.
+    Io(io::Error),
 }
 
-impl<'a> AsBytes for &'a str {
-    #[inline(always)]
-    fn as_bytes(&self) -> Vec<u8> {
-        (*self).as_bytes().into()
+impl<S> PlayerError<S> {
+    /// Returns `true` if the turn can be retried.
+    pub fn is_recoverable(&self) -> bool {
+        matches!(self, PlayerError::Invalid(..))
     }
 }
 
-impl AsBytes for str {
-    #[inline(always)]
-    fn as_bytes(&self) -> Vec<u8> {
-        let b: &[u8] = self.as_ref();
-        b.into()
+impl<S> From<io::Error> for PlayerError<S> {
+    fn from(err: io::Error) -> Self {
+        PlayerError::Io(err)
     }
 }
 
-impl<'a> AsBytes for &'a [u8] {
-    #[inline(always)]
-    fn as_bytes(&self) -> Vec<u8> {
-        (*self).into()
+impl<S> fmt::Display for PlayerError<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlayerError::Invalid(_, reason) => write!(f, "{}", reason),
+            PlayerError::Io(err) => write!(f, "{}", err),
+        }
     }
 }
 
-impl AsBytes for [u8] {
-    #[inline(always)]
-    fn as_bytes(&self) -> Vec<u8> {
-        self.into()
+impl<S: fmt::Debug> error::Error for PlayerError<S> {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            PlayerError::Invalid(..) => None,
+            PlayerError::Io(err) => Some(err),
+        }
     }
 }
@@ -1,43 +1,164 @@
 mod cmd;
-mod numberguesser;
-mod tictactoe;
 
-use std::io;
+use std::fmt::Display;
+use std::fs::{self, File};
+use std::io::{self, BufWriter};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::str::FromStr;
+use std::thread;
+use std::time::Duration;
 
+use ax::ai::nn::Optimizer;
+use ax::ai::Negamax;
 use ax::combinator::{
-    enumerate_action, if_then_else, map_action, map_err, map_result, render, repeat_until_terminal,
-    take_turn, Either,
+    map_action, map_err, record, render, repeat_until_terminal, retry, take_turn, turns, Retry,
 };
-use ax::{Action, AsBytes, Player, State};
+use ax::config::{self, Config};
+use ax::render::{Render, Style};
+use ax::seed::{Seed, Streams};
+use ax::snapshot::{self, Position, Snapshot};
+use ax::transcript::Transcript;
+use ax::viewer::Viewer;
+use ax::{numberguesser, players, ratings, sprt, tictactoe, tournament, train, viewer};
+use ax::{Action, Game, Player, PlayerError, State};
+use clap::ArgMatches;
+use tracing::{info, Level};
 
-use rand;
+/// Tell a player why their turn was rejected.
+fn report(reason: &str) {
+    println!("{}", reason);
+}
+
+/// Returns the value of a flag given on the command line, or `configured` if it wasn't.
+fn flag<T: FromStr>(args: &ArgMatches, name: &str, configured: T) -> T
+where
+    T::Err: Display,
+{
+    if args.occurrences_of(name) > 0 {
+        args.value_of_t_or_exit(name)
+    } else {
+        configured
+    }
+}
+
+/// Builds the options AI are created with from the flags in `args`,
+/// falling back to the config.
+fn options(args: &ArgMatches, config: &Config, streams: Streams) -> players::Options {
+    players::Options {
+        depth: flag(args, "depth", config.negamax.depth),
+        iterations: config.mcts.iterations,
+        exploration: config.mcts.exploration,
+        model: flag(args, "model", PathBuf::from(&config.tic_tac_toe.model)),
+        streams,
+    }
+}
+
+/// Names the player in each seat of a tic-tac-toe game played in `mode`.
+fn seats<'a>(mode: &str, ais: &'a [String], ai_first: bool) -> Result<[&'a str; 2], String> {
+    match (mode, ais.first(), ais.last()) {
+        ("human-vs-ai", Some(ai), _) if ai_first => Ok([ai, "human"]),
+        ("human-vs-ai", Some(ai), _) => Ok(["human", ai]),
+        ("human-vs-human", _, _) => Ok(["human", "human"]),
+        ("ai-vs-ai", Some(first), Some(second)) => Ok([first, second]),
+        ("human-vs-ai", None, _) | ("ai-vs-ai", _, _) => Err(format!("{} needs an AI", mode)),
+        (mode, _, _) => Err(format!("unknown mode: {}", mode)),
+    }
+}
+
+/// Log to stderr, with more detail the more often `-v` was given.
+fn init_logging(verbosity: u64, format: &str) {
+    let level = match verbosity {
+        0 => Level::WARN,
+        1 => Level::INFO,
+        2 => Level::DEBUG,
+        _ => Level::TRACE,
+    };
+    let logger = tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_writer(io::stderr);
+    match format {
+        "json" => logger.json().init(),
+        _ => logger.init(),
+    }
+}
+
+/// Records the turns taken by `action` to the file given with `--record`, if any.
+fn recorded<'a, AE, SE: 'a, S>(
+    args: &ArgMatches,
+    mut action: impl Action<AE, SE, S, S> + 'a,
+) -> Box<dyn FnMut(S) -> Result<S, AE> + 'a>
+where
+    S: Game<SE> + Position + 'a,
+    S::Move: Display,
+    AE: From<io::Error> + 'a,
+{
+    match args.value_of("record") {
+        Some(path) => match File::create(path) {
+            Ok(file) => Box::new(record(BufWriter::new(file), action)),
+            Err(err) => {
+                eprintln!("ax: {}: {}", path, err);
+                process::exit(1);
+            }
+        },
+        None => Box::new(move |state| action.apply(state)),
+    }
+}
 
-fn play_tic_tac_toe<SE, S>(p1: impl Player<S>, p2: impl Player<S>) -> impl FnMut(S) -> Result<S, ()>
+/// Loads a saved game and the streams to resume it with, exiting if it can't be resumed.
+fn resume<S: Snapshot>(path: &str, new: S) -> (S, Streams) {
+    let (state, streams) = snapshot::load(Path::new(path), new).unwrap_or_else(|err| {
+        eprintln!("ax: {}: {}", path, err);
+        process::exit(1);
+    });
+    info!(path, moves = state.moves(), seed = %streams.seed(), "resumed a saved game");
+    (state, streams)
+}
+
+/// Reads the transcript in `text` into a viewer, exiting if it isn't a game of `new`.
+fn view<E, S>(args: &ArgMatches, path: &str, text: &[u8], new: S) -> Viewer<S, S::Move>
 where
-    S: State<SE> + AsBytes,
+    S: Game<E> + Position + Render + Clone,
+    S::Move: Display + FromStr,
 {
-    let mut p1 = take_turn(p1);
-    let mut p2 = take_turn(p2);
+    Transcript::read(text)
+        .and_then(|transcript| Viewer::new(new, transcript))
+        .map(|viewer| viewer.style(args.value_of_t_or_exit("style")))
+        .unwrap_or_else(|err| {
+            eprintln!("replay: {}: {}", path, err);
+            process::exit(1);
+        })
+}
+
+/// Steps through a replay as asked on the command line.
+fn watch<S: Render, M: Display + Clone + PartialEq>(args: &ArgMatches, mut replay: Viewer<S, M>) {
+    let delay = Duration::from_millis(args.value_of_t_or_exit("delay"));
+    let stdout = io::stdout();
+    let shown = if args.is_present("play") {
+        replay
+            .show(stdout.lock())
+            .and_then(|_| replay.play(delay, stdout.lock()))
+    } else {
+        replay.run(io::stdin().lock(), stdout.lock(), delay)
+    };
+    if let Err(err) = shown {
+        eprintln!("replay: {}", err);
+        process::exit(1);
+    }
+}
 
+fn play_tic_tac_toe<SE: 'static, S>(
+    args: &ArgMatches,
+    players: Vec<Box<dyn Player<S>>>,
+    style: Style,
+) -> impl FnMut(S) -> Result<S, PlayerError<S>>
+where
+    S: Game<SE> + Position + Render + 'static,
+    S::Move: Display,
+{
     repeat_until_terminal(map_action(
-        map_result(
-            enumerate_action(if_then_else(
-                |(i, _)| i % 2 == 0,
-                move |(_, s)| p1(s),
-                move |(_, s)| p2(s),
-            )),
-            |r| match r {
-                Result::Ok(e) => Ok(match e {
-                    Either::Left(s) => s,
-                    Either::Right(s) => s,
-                }),
-                Result::Err(e) => Err(match e {
-                    Either::Left(e) => e,
-                    Either::Right(e) => e,
-                }),
-            },
-        ),
-        map_err(render(io::stdout()), |_| ()),
+        recorded(args, retry(turns(players), Retry::Forever, report)),
+        map_err(render(io::stdout(), style), PlayerError::Io),
     ))
 }
 
@@ -46,70 +167,359 @@ fn main() {
 
     let train = cmd::build_train(vec![cmd::build_nn(), cmd::build_rnn()]);
 
-    let ax = cmd::build_cli(vec![play, train]);
+    let ax = cmd::build_cli(vec![
+        play,
+        train,
+        cmd::build_tournament(),
+        cmd::build_sprt(),
+        cmd::build_replay(),
+        cmd::build_ratings(),
+        cmd::build_config(),
+    ]);
 
     let args = ax.get_matches();
 
+    init_logging(
+        args.occurrences_of("verbosity"),
+        args.value_of("log-format").unwrap(),
+    );
+
+    let path = args.value_of("config").unwrap();
+    if let Some(("config", args)) = args.subcommand() {
+        if let Some(("init", args)) = args.subcommand() {
+            if Path::new(path).exists() && !args.is_present("force") {
+                eprintln!(
+                    "config: {} already exists, use --force to overwrite it",
+                    path
+                );
+                process::exit(1);
+            }
+            if let Err(err) = fs::write(path, config::DEFAULT) {
+                eprintln!("config: {}: {}", path, err);
+                process::exit(1);
+            }
+            println!("wrote default config to {}", path);
+        }
+        return;
+    }
+
+    let config =
+        Config::load(Path::new(path), args.occurrences_of("config") > 0).unwrap_or_else(|err| {
+            eprintln!("ax: {}: {}", path, err);
+            process::exit(1);
+        });
+
+    let seed = Seed::new(if args.is_present("seed") {
+        Some(args.value_of_t_or_exit("seed"))
+    } else {
+        config.seed
+    });
+    info!(%seed, "seeded random number generators");
+
     match args.subcommand() {
         None => {}
         Some(("play", args)) => match args.subcommand() {
             None => {}
             Some(("number-guesser", args)) => {
-                let high: i64 = args.value_of("high").unwrap().parse().unwrap();
-                let low: i64 = args.value_of("low").unwrap().parse().unwrap();
+                let high: i64 = flag(args, "high", config.number_guesser.high);
+                let low: i64 = flag(args, "low", config.number_guesser.low);
+                if low >= high {
+                    eprintln!("number-guesser: high must be greater than low");
+                    process::exit(1);
+                }
 
-                let rng = rand::thread_rng();
-                let state = numberguesser::State::new(low, high, rng);
+                let (state, streams) = match args.value_of("resume") {
+                    Some(path) => resume(path, numberguesser::State::with_number(low, high, low)),
+                    None => {
+                        let streams = Streams::new(seed);
+                        let rng = streams.stream("number-guesser");
+                        (numberguesser::State::new(low, high, rng), streams)
+                    }
+                };
+
+                let strategy = match (args.value_of("ai"), args.is_present("model")) {
+                    (Some(strategy), _) => strategy,
+                    (None, true) => "model",
+                    (None, false) => "human",
+                };
+                let model = args.value_of("model").unwrap_or("number-guesser.model");
+                let player = players::number_guesser(strategy, Path::new(model), &streams)
+                    .unwrap_or_else(|err| {
+                        eprintln!("ax: failed to load model: {}", err);
+                        process::exit(1);
+                    });
 
                 let mut run = repeat_until_terminal(map_action(
-                    take_turn(numberguesser::Human),
-                    map_err(render(io::stdout()), |_| ()),
+                    recorded(args, retry(take_turn(player), Retry::Forever, report)),
+                    map_err(
+                        render(io::stdout(), args.value_of_t_or_exit("style")),
+                        PlayerError::Io,
+                    ),
                 ));
 
-                run.apply(state).expect("should have succeeded");
+                match run.apply(state) {
+                    Ok(state) => match strategy {
+                        "human" => println!("You needed {} guesses.", state.history().len()),
+                        _ => println!("{} needed {} guesses.", strategy, state.history().len()),
+                    },
+                    Err(err) => {
+                        eprintln!("number-guesser: {}", err);
+                        process::exit(1);
+                    }
+                }
             }
             Some(("tic-tac-toe", args)) => {
-                let mode: &str = args.value_of("mode").unwrap();
-                let ais: Vec<&str> = args.values_of("ai").unwrap().collect();
-
-                let state: tictactoe::Board<&str> = tictactoe::Board::new();
-
-                let state = match mode {
-                    "human-vs-ai" => {
-                        let human = tictactoe::Human("X");
-
-                        let mut run = match ais[0] {
-                            "random" => play_tic_tac_toe(
-                                human,
-                                tictactoe::Random::new("O", rand::thread_rng()),
-                            ),
-                            s => panic!("human-vs-ai: unsupported ai: {}", s),
-                        };
-                        run.apply(state)
-                    }
-                    "human-vs-human" => {
-                        let mut run =
-                            play_tic_tac_toe(tictactoe::Human("X"), tictactoe::Human("O"));
+                let mode: String = flag(args, "mode", config.tic_tac_toe.mode.clone());
+                let ais: Vec<String> = if args.occurrences_of("ai") > 0 {
+                    args.values_of_t_or_exit("ai")
+                } else {
+                    config.tic_tac_toe.ai.clone()
+                };
+                let ai_first = if args.is_present("ai-first") {
+                    true
+                } else if args.is_present("human-first") {
+                    false
+                } else {
+                    config.tic_tac_toe.ai_first
+                };
 
-                        run.apply(state)
+                let pieces = ["X", "O"];
+                let board = tictactoe::Board::new(pieces[0], pieces[1]);
+                let (state, streams) = match args.value_of("resume") {
+                    Some(path) => {
+                        let (board, streams) = resume(path, board);
+                        print!("{}", board);
+                        (board, streams)
                     }
-                    "ai-vs-ai" => {
-                        let mut run = play_tic_tac_toe(
-                            tictactoe::Random::new("X", rand::thread_rng()),
-                            tictactoe::Random::new("O", rand::thread_rng()),
-                        );
+                    None => (board, Streams::new(seed)),
+                };
+                let options = options(args, &config, streams);
+
+                let names = seats(&mode, &ais, ai_first).unwrap_or_else(|err| {
+                    eprintln!("tic-tac-toe: {}", err);
+                    process::exit(1);
+                });
+                let players = names
+                    .iter()
+                    .enumerate()
+                    .map(|(seat, name)| players::tic_tac_toe(name, seat, &options))
+                    .collect::<Result<Vec<_>, _>>()
+                    .unwrap_or_else(|err| {
+                        eprintln!("tic-tac-toe: {}", err);
+                        process::exit(1);
+                    });
 
-                        run.apply(state)
+                let state =
+                    play_tic_tac_toe(args, players, args.value_of_t_or_exit("style")).apply(state);
+
+                let state = match state {
+                    Ok(state) => state,
+                    Err(err) => {
+                        eprintln!("tic-tac-toe: {}", err);
+                        process::exit(1);
                     }
-                    _ => Err(()),
                 };
-
-                state.expect("failed");
+                match state.status() {
+                    Ok(ax::Status::Terminal(outcome)) => match outcome.winner() {
+                        Some(seat) => println!("{} wins!", pieces[seat]),
+                        None => println!("It's a draw."),
+                    },
+                    _ => panic!("tic-tac-toe: game ended early"),
+                }
             }
             Some((s, _)) => panic!("play: unknown command: {}", s),
         },
-        Some(("train", _args)) => {
-            todo!();
+        Some(("train", args)) => {
+            let (model, args) = args.subcommand().unwrap();
+            let game = args
+                .subcommand_matches("for")
+                .unwrap()
+                .value_of("game")
+                .unwrap();
+
+            let defaults = &config.train;
+            let rate = flag(args, "rate", defaults.learning_rate);
+            let optimizer: String = flag(args, "optimizer", defaults.optimizer.clone());
+            let settings = train::Settings {
+                epochs: flag(args, "epochs", defaults.epochs),
+                games: flag(args, "games", defaults.games),
+                population: flag(args, "population", defaults.population),
+                layers: if args.is_present("layer") {
+                    args.values_of_t_or_exit("layer")
+                } else {
+                    defaults.layers.clone()
+                },
+                activation: flag(args, "activation", defaults.activation),
+                loss: flag(args, "loss", defaults.loss),
+                optimizer: match optimizer.as_str() {
+                    "adam" => Optimizer::adam(rate),
+                    _ => Optimizer::sgd(rate),
+                },
+                recorded: args.value_of("from").map(PathBuf::from),
+                seed,
+            };
+            let output = match args.value_of("output") {
+                Some(path) => PathBuf::from(path),
+                None => PathBuf::from(format!("{}.model", game)),
+            };
+
+            let alg = args.value_of("alg").unwrap();
+            if let Err(err) = train::train(model, alg, game, &settings, &output) {
+                eprintln!("train: {}", err);
+                process::exit(1);
+            }
+        }
+        Some(("tournament", args)) => {
+            let tournament = tournament::Tournament {
+                ais: args.values_of_t_or_exit("ai"),
+                games: args.value_of_t_or_exit("games"),
+                threads: if args.is_present("threads") {
+                    args.value_of_t_or_exit("threads")
+                } else {
+                    thread::available_parallelism().map_or(1, |n| n.get())
+                },
+                options: options(args, &config, Streams::new(seed)),
+            };
+            let format = args.value_of_t_or_exit("format");
+
+            let pairings = tournament.run().unwrap_or_else(|err| {
+                eprintln!("tournament: {}", err);
+                process::exit(1);
+            });
+            if let Err(err) = tournament::write(&pairings, format, io::stdout()) {
+                eprintln!("tournament: {}", err);
+                process::exit(1);
+            }
+
+            if !args.is_present("unrated") {
+                let path = Path::new(args.value_of("ratings").unwrap());
+                let rate = || -> io::Result<()> {
+                    let identities = tournament
+                        .ais
+                        .iter()
+                        .map(|ai| Ok((ai.clone(), ratings::identity(ai, &tournament.options)?)))
+                        .collect::<io::Result<Vec<_>>>()?;
+                    let mut ledger = ratings::Ledger::load(path)?;
+                    ledger.record(&pairings, &identities);
+                    ledger.save(path)
+                };
+                if let Err(err) = rate() {
+                    eprintln!("ratings: {}", err);
+                    process::exit(1);
+                }
+                info!(path = %path.display(), "updated ratings");
+            }
+        }
+        Some(("sprt", args)) => {
+            let test = sprt::Sprt {
+                baseline: args.value_of_t_or_exit("baseline"),
+                candidate: args.value_of_t_or_exit("candidate"),
+                bounds: sprt::Bounds {
+                    elo0: args.value_of_t_or_exit("elo0"),
+                    elo1: args.value_of_t_or_exit("elo1"),
+                    alpha: args.value_of_t_or_exit("alpha"),
+                    beta: args.value_of_t_or_exit("beta"),
+                },
+                max_games: args.value_of_t_or_exit("max-games"),
+                options: options(args, &config, Streams::new(seed)),
+            };
+            if let Err(err) = test.bounds.check() {
+                eprintln!("sprt: {}", err);
+                process::exit(1);
+            }
+            let (lower, upper) = (test.bounds.lower(), test.bounds.upper());
+            println!(
+                "H0: elo {}, H1: elo {}, LLR bounds [{:.2}, {:.2}]",
+                test.bounds.elo0, test.bounds.elo1, lower, upper
+            );
+
+            let result = test
+                .run(|progress| {
+                    let record = progress.record;
+                    println!(
+                        "games {:>5}  +{} ={} -{}  LLR {:>6.2}",
+                        record.games(),
+                        record.wins,
+                        record.draws,
+                        record.losses,
+                        progress.llr
+                    );
+                })
+                .unwrap_or_else(|err| {
+                    eprintln!("sprt: {}", err);
+                    process::exit(1);
+                });
+
+            let record = result.record;
+            match result.verdict {
+                Some(sprt::Verdict::H1) => println!(
+                    "H1 accepted after {} games: {} is stronger than {}.",
+                    record.games(),
+                    test.candidate,
+                    test.baseline
+                ),
+                Some(sprt::Verdict::H0) => println!(
+                    "H0 accepted after {} games: {} is not stronger than {}.",
+                    record.games(),
+                    test.candidate,
+                    test.baseline
+                ),
+                None => println!("No verdict after {} games.", record.games()),
+            }
+            println!(
+                "Elo difference: {:+.1} [{:+.1}, {:+.1}]",
+                sprt::elo(record.score()),
+                sprt::elo(record.score() - record.margin()),
+                sprt::elo(record.score() + record.margin())
+            );
+        }
+        Some(("replay", args)) => {
+            let path = args.value_of("file").unwrap();
+            let text = fs::read(path).unwrap_or_else(|err| {
+                eprintln!("replay: {}: {}", path, err);
+                process::exit(1);
+            });
+            let game = Transcript::<String>::read(&text[..])
+                .map(|transcript| transcript.game)
+                .unwrap_or_else(|err| {
+                    eprintln!("replay: {}: {}", path, err);
+                    process::exit(1);
+                });
+
+            if game == numberguesser::State::GAME {
+                let state = numberguesser::State::with_number(0, 1, 0);
+                watch(args, view(args, path, &text, state));
+                return;
+            }
+            if game != tictactoe::Board::<&str>::GAME {
+                eprintln!("replay: {}: unknown game {:?}", path, game);
+                process::exit(1);
+            }
+            let mut replay = view(args, path, &text, tictactoe::Board::new("X", "O"));
+            if !args.is_present("no-eval") {
+                let depth = flag(args, "depth", config.negamax.depth);
+                let mut searches = [0, 1].map(|seat| {
+                    Negamax::<(), _, _>::with_hueristic(
+                        seat,
+                        depth,
+                        move |b: &tictactoe::Board<&str>| b.hueristic(seat),
+                    )
+                    .transposition_table(players::TABLE_SIZE)
+                });
+                replay.evaluate(|board| {
+                    viewer::evaluate(&mut searches[board.current_player()], board)
+                });
+            }
+            watch(args, replay);
+        }
+        Some(("ratings", args)) => {
+            let path = Path::new(args.value_of("ratings").unwrap());
+            let shown = ratings::Ledger::load(path)
+                .and_then(|ledger| ratings::write_table(&ledger, io::stdout()));
+            if let Err(err) = shown {
+                eprintln!("ratings: {}", err);
+                process::exit(1);
+            }
         }
         Some((s, _)) => panic!("ax: unknown command: {}", s),
     }
@@ -1,14 +1,19 @@
+use std::cmp::Ordering;
 use std::error;
 use std::fmt;
 use std::io::{self, Write};
-
-use ax;
+use std::path::Path;
 
 use rand::Rng;
 
-#[derive(Debug, PartialEq, Copy, Clone)]
+use crate as ax;
+use crate::render::{ansi, Render, Style};
+use crate::Game;
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct State {
-    guess: Option<i64>,
+    guesses: Vec<i64>,
     number: i64, // the number to guess
     low: i64,
     high: i64,
@@ -26,7 +31,7 @@ impl State {
     /// Initializes a game state where the player must guess the provided number.
     pub fn with_number(low: i64, high: i64, number: i64) -> Self {
         Self {
-            guess: None,
+            guesses: Vec::new(),
             number,
             low,
             high,
@@ -34,39 +39,165 @@ impl State {
     }
 
     /// Allows the player to make a guess.
-    pub fn guess(mut self, n: i64) -> Self {
-        self.guess = Some(n);
-        self
+    pub fn guess(&mut self, n: i64) -> Result<(), Error> {
+        if !(self.low..self.high).contains(&n) {
+            return Err(Error::OutOfRange {
+                guess: n,
+                low: self.low,
+                high: self.high,
+            });
+        }
+        self.guesses.push(n);
+        Ok(())
+    }
+
+    /// Returns the feedback on guessing `n`.
+    fn hint(&self, n: i64) -> Hint {
+        match n.cmp(&self.number) {
+            Ordering::Less => Hint::TooLow,
+            Ordering::Greater => Hint::TooHigh,
+            Ordering::Equal => Hint::Correct,
+        }
+    }
+
+    /// Returns every guess so far along with the feedback it got.
+    pub fn history(&self) -> Vec<(i64, Hint)> {
+        self.guesses.iter().map(|g| (*g, self.hint(*g))).collect()
+    }
+
+    /// Returns the lowest and highest number the answer can still be,
+    /// given the feedback on every guess so far.
+    pub fn bounds(&self) -> (i64, i64) {
+        self.history()
+            .into_iter()
+            .fold(
+                (self.low, self.high - 1),
+                |(low, high), (guess, hint)| match hint {
+                    Hint::TooLow => (low.max(guess + 1), high),
+                    Hint::TooHigh => (low, high.min(guess - 1)),
+                    Hint::Correct => (guess, guess),
+                },
+            )
+    }
+
+    /// Returns the range of numbers which can be guessed.
+    pub fn range(&self) -> (i64, i64) {
+        (self.low, self.high)
+    }
+
+    /// Returns the number to guess.
+    pub fn number(&self) -> i64 {
+        self.number
+    }
+
+    /// Returns the most recent guess.
+    pub fn last_guess(&self) -> Option<i64> {
+        self.guesses.last().copied()
+    }
+}
+
+/// Feedback on a guess.
+#[derive(Debug, PartialEq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Hint {
+    TooLow,
+    TooHigh,
+    Correct,
+}
+
+impl fmt::Display for Hint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Hint::TooLow => write!(f, "Too low."),
+            Hint::TooHigh => write!(f, "Too high."),
+            Hint::Correct => write!(f, "Correct!"),
+        }
     }
 }
 
 impl fmt::Display for State {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let guess = self.guess.unwrap();
-        if guess > self.number {
-            writeln!(f, "Too high.")
-        } else if guess < self.number {
-            writeln!(f, "Too low.")
-        } else {
-            writeln!(f, "Correct!")
+        let guess = self.last_guess().unwrap();
+        writeln!(f, "{}", self.hint(guess))
+    }
+}
+
+impl Hint {
+    /// Names the hint in a word, for the compact and JSON styles.
+    fn word(self) -> &'static str {
+        match self {
+            Hint::TooLow => "too-low",
+            Hint::TooHigh => "too-high",
+            Hint::Correct => "correct",
+        }
+    }
+}
+
+impl Render for State {
+    fn render(&self, style: Style, out: &mut dyn fmt::Write) -> fmt::Result {
+        let last = self.history().last().copied();
+        match (style, last) {
+            (Style::Json, _) => {
+                let guesses: Vec<String> = self.guesses.iter().map(|g| g.to_string()).collect();
+                write!(
+                    out,
+                    "{{\"range\":[{},{}],\"guesses\":[{}],\"hint\":",
+                    self.low,
+                    self.high,
+                    guesses.join(",")
+                )?;
+                match last {
+                    Some((_, hint)) => writeln!(out, "\"{}\"}}", hint.word()),
+                    None => writeln!(out, "null}}"),
+                }
+            }
+            (_, None) => writeln!(out, "No guesses yet."),
+            (Style::Plain, Some(_)) => write!(out, "{}", self),
+            (Style::Ansi, Some((_, hint))) => {
+                let colour = match hint {
+                    Hint::Correct => ansi::GREEN,
+                    _ => ansi::YELLOW,
+                };
+                writeln!(out, "{}{}{}{}", ansi::BOLD, colour, hint, ansi::RESET)
+            }
+            (Style::Compact, Some((guess, hint))) => writeln!(
+                out,
+                "guess {}: {} {}",
+                self.guesses.len(),
+                guess,
+                hint.word()
+            ),
         }
     }
 }
 
-impl ax::AsBytes for State {
-    fn as_bytes(&self) -> Vec<u8> {
-        let mut s = String::new();
-        fmt::write(&mut s, format_args!("{}", self)).expect("unexpected error");
-        s.into_bytes()
+#[derive(Debug)]
+pub enum Error {
+    OutOfRange { guess: i64, low: i64, high: i64 },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::OutOfRange { guess, low, high } => write!(
+                f,
+                "{} is out of range, pick a number from {} to {}",
+                guess,
+                low,
+                high - 1
+            ),
+        }
     }
 }
 
+impl error::Error for Error {}
+
 #[derive(Debug)]
 pub struct NoGuess;
 
 impl fmt::Display for NoGuess {
-    fn fmt(&self, _f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        todo!()
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "no guess has been made yet")
     }
 }
 
@@ -74,37 +205,155 @@ impl error::Error for NoGuess {}
 
 impl ax::State<NoGuess> for State {
     fn status(&self) -> Result<ax::Status, NoGuess> {
-        self.guess
-            .and_then(|guess| {
-                if guess > self.number {
-                    Some(ax::Status::Valid)
-                } else if guess < self.number {
-                    Some(ax::Status::Valid)
+        self.last_guess()
+            .map(|guess| {
+                if guess != self.number {
+                    ax::Status::Valid
                 } else {
-                    Some(ax::Status::Terminal)
+                    ax::Status::Terminal(ax::Outcome::Winner(0))
                 }
             })
             .ok_or(NoGuess)
     }
 }
 
-#[derive(Debug, Copy, Clone)]
-pub struct Human;
+/// The most guesses [`Game::legal_moves`] lists.
+pub const MAX_MOVES: i64 = 1024;
+
+impl ax::Game<NoGuess> for State {
+    type Move = i64;
+
+    /// Lists the guesses the answer can still be, see [`State::bounds`].
+    /// Any other guess in range is legal too, but is never worth making.
+    ///
+    /// If there are more than [`MAX_MOVES`] of them, only that many evenly
+    /// spaced guesses are listed, so wide ranges don't have to be collected.
+    fn legal_moves(&self) -> Vec<i64> {
+        if self.last_guess() == Some(self.number) {
+            return Vec::new();
+        }
+        let (low, high) = self.bounds();
+        let span = i128::from(high) - i128::from(low);
+        if span < i128::from(MAX_MOVES) {
+            return (low..=high).collect();
+        }
+        (0..i128::from(MAX_MOVES))
+            .map(|i| (i128::from(low) + span * i / i128::from(MAX_MOVES - 1)) as i64)
+            .collect()
+    }
+
+    fn apply(&mut self, mv: i64) {
+        self.guess(mv).expect("guess should be legal");
+    }
+
+    fn undo(&mut self, mv: i64) {
+        let last = self.guesses.pop();
+        debug_assert_eq!(last, Some(mv), "can only undo the last guess");
+    }
+
+    fn is_legal(&self, mv: &i64) -> bool {
+        self.last_guess() != Some(self.number) && (self.low..self.high).contains(mv)
+    }
+
+    fn last_move(&self) -> Option<i64> {
+        self.last_guess()
+    }
+}
+
+/// Where [`Human`] saves the game if they don't name a file.
+pub const SAVE_FILE: &str = "number-guesser.save";
+
+/// Saves a game to a file.
+type Save = Box<dyn FnMut(&State, &Path) -> io::Result<()>>;
+
+/// Besides a number, a human can enter `save [FILE]` to save the game,
+/// after which they are asked to guess again.
+#[derive(Default)]
+pub struct Human {
+    save: Option<Save>,
+}
+
+impl Human {
+    /// Lets the human save the game with `save`.
+    pub fn on_save(mut self, save: impl FnMut(&State, &Path) -> io::Result<()> + 'static) -> Self {
+        self.save = Some(Box::new(save));
+        self
+    }
+}
 
 impl ax::Player<State> for Human {
-    fn take_turn(&mut self, state: State) -> State {
+    fn take_turn(&mut self, state: State) -> Result<State, ax::PlayerError<State>> {
         let mut input = String::new();
+        loop {
+            io::stdout().write_all(b"Guess a number: ")?;
+            io::stdout().flush()?;
+            input.clear();
+            if io::stdin().read_line(&mut input)? == 0 {
+                return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+            }
+
+            // Saving isn't a move, so ask again without using up a try.
+            let mut words = input.split_whitespace();
+            match (words.next(), words.next()) {
+                (Some("save"), path) => {
+                    let path = Path::new(path.unwrap_or(SAVE_FILE));
+                    match self.save.as_mut().map(|save| save(&state, path)) {
+                        Some(Ok(())) => println!("Saved the game to {}.", path.display()),
+                        Some(Err(err)) => println!("Failed to save the game: {}", err),
+                        None => println!("This game can't be saved."),
+                    }
+                }
+                _ => break,
+            }
+        }
+
+        let guess = match input.trim().parse() {
+            Ok(guess) => guess,
+            Err(_) => {
+                let reason = format!("{:?} is not a number", input.trim());
+                return Err(ax::PlayerError::Invalid(state, reason));
+            }
+        };
+
+        let mut state = state;
+        match state.guess(guess) {
+            Ok(()) => Ok(state),
+            Err(err) => Err(ax::PlayerError::Invalid(state, err.to_string())),
+        }
+    }
+}
+
+/// This AI halves the range the number can still be in with every guess.
+#[derive(Debug, Copy, Clone)]
+pub struct BinarySearch;
+
+impl ax::Player<State> for BinarySearch {
+    fn take_turn(&mut self, state: State) -> Result<State, ax::PlayerError<State>> {
+        let (low, high) = state.bounds();
+        let mut state = state;
+        state.apply(low + (high - low) / 2);
+        Ok(state)
+    }
+}
 
-        io::stdout()
-            .write_all(b"Guess a number: ")
-            .expect("failed to write input message");
-        io::stdout().flush().expect("failed to flush input message");
-        io::stdin()
-            .read_line(&mut input)
-            .expect("failed to read user input");
+/// This AI guesses any number the answer can still be, at random.
+#[derive(Debug)]
+pub struct RandomInRange<R: Rng> {
+    rng: R,
+}
 
-        let guess = input.trim();
+impl<R: Rng> RandomInRange<R> {
+    pub fn new(rng: R) -> Self {
+        Self { rng }
+    }
+}
 
-        state.guess(guess.parse().unwrap())
+impl<R: Rng> ax::Player<State> for RandomInRange<R> {
+    fn take_turn(&mut self, state: State) -> Result<State, ax::PlayerError<State>> {
+        let (low, high) = state.bounds();
+        let guess = self.rng.gen_range(low..=high);
+        let mut state = state;
+        state.apply(guess);
+        Ok(state)
     }
 }
@@ -0,0 +1,100 @@
+//! Creates the players picked on the command line.
+
+use std::path::{Path, PathBuf};
+
+use crate::ai::{Budget, Mcts, Negamax};
+use crate::numberguesser::{self, State};
+use crate::seed::Streams;
+use crate::snapshot;
+use crate::tictactoe::{self, Board};
+use crate::train;
+use crate::Player;
+
+/// The names of the tic-tac-toe players which aren't model files.
+pub const TIC_TAC_TOE: &[&str] = &["human", "random", "negamax", "mcts", "nn", "neatnn"];
+
+/// Room for this many states in the transposition table used by negamax.
+pub const TABLE_SIZE: usize = 1 << 16;
+
+/// Settings of the AI which can be picked.
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// How many moves ahead negamax searches.
+    pub depth: usize,
+    /// Playouts run by mcts for each move.
+    pub iterations: u64,
+    pub exploration: f64,
+    /// Model file used by `nn` and `neatnn`.
+    pub model: PathBuf,
+    /// Each seat draws from its own one of these streams.
+    pub streams: Streams,
+}
+
+/// Creates a tic-tac-toe player for `seat` from its name: `human`, `random`,
+/// `negamax`, `mcts`, `nn`, `neatnn` or the path of a model file, which has
+/// to hold a path separator or `.model` so a typo isn't taken for one.
+pub fn tic_tac_toe<P>(
+    name: &str,
+    seat: usize,
+    options: &Options,
+) -> Result<Box<dyn Player<Board<P>>>, train::Error>
+where
+    P: std::fmt::Display + PartialEq + Default + Copy + 'static,
+{
+    let rng = options
+        .streams
+        .stream(&format!("tic-tac-toe seat {}", seat));
+    Ok(match name {
+        "human" => {
+            let streams = options.streams.clone();
+            Box::new(
+                tictactoe::Human::default()
+                    .on_save(move |board, path| snapshot::save(path, board, &streams)),
+            )
+        }
+        "random" => Box::new(tictactoe::Random::new(rng)),
+        "negamax" => Box::new(
+            Negamax::<(), _, _>::with_hueristic(seat, options.depth, move |b: &Board<P>| {
+                b.hueristic(seat)
+            })
+            .transposition_table(TABLE_SIZE),
+        ),
+        "mcts" => Box::new(
+            Mcts::new(rng)
+                .budget(Budget::Nodes(options.iterations))
+                .exploration(options.exploration),
+        ),
+        "nn" | "neatnn" => train::load_tic_tac_toe(&options.model, rng)?,
+        path if path.contains(std::path::is_separator) || path.contains(".model") => {
+            train::load_tic_tac_toe(Path::new(path), rng)?
+        }
+        name => {
+            return Err(train::Error::Unsupported(format!(
+                "unknown AI {:?}, expected one of {} or the path of a model file",
+                name,
+                TIC_TAC_TOE.join(", ")
+            )))
+        }
+    })
+}
+
+/// Creates a number-guesser player from the name of its strategy: `human`,
+/// `binary-search`, `random` or `model`, which plays the model at `model`.
+pub fn number_guesser(
+    name: &str,
+    model: &Path,
+    streams: &Streams,
+) -> Result<Box<dyn Player<State>>, train::Error> {
+    let rng = streams.stream("number-guesser player");
+    let streams = streams.clone();
+    Ok(match name {
+        "human" => Box::new(
+            numberguesser::Human::default()
+                .on_save(move |state, path| snapshot::save(path, state, &streams)),
+        ),
+        "binary-search" => Box::new(numberguesser::BinarySearch),
+        "random" => Box::new(numberguesser::RandomInRange::new(rng)),
+        "model" => train::load_guesser(model, rng)?,
+        s => panic!("number-guesser: unknown strategy: {}", s),
+    })
+}
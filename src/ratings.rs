@@ -0,0 +1,346 @@
+//! Ratings of players which persist across tournaments.
+//!
+//! Players are rated with both Elo and Glicko-2, and kept in a ledger file
+//! with a header line followed by one line per player:
+//!
+//! ```text
+//! ax-ratings 1
+//! player negamax:depth=9 1516.0 1620.3 94.2 0.059998 40 negamax
+//! ```
+//!
+//! Each player line holds the identity of the player, its Elo rating, its
+//! Glicko-2 rating, deviation and volatility, how many games it has played
+//! and the name it was last entered under.
+
+use std::f64::consts::PI;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use crate::players::Options;
+use crate::seed::fnv1a;
+use crate::tournament::Pairing;
+
+/// The version of the format written by [`Ledger::write`].
+pub const VERSION: u32 = 1;
+
+/// How far a single game moves an Elo rating.
+const K: f64 = 32.0;
+
+/// Converts Glicko ratings to the Glicko-2 scale and back.
+const SCALE: f64 = 173.7178;
+
+/// Limits how quickly the volatility of a Glicko-2 rating can change.
+const TAU: f64 = 0.5;
+
+/// Returns the identity a player is rated under: the name of a built-in AI
+/// along with its settings, or a hash of the contents of a model file, so a
+/// model keeps its rating when it is moved and a retrained one gets its own.
+pub fn identity(name: &str, options: &Options) -> io::Result<String> {
+    let model = |path: &Path| -> io::Result<String> {
+        let bytes = fs::read(path)
+            .map_err(|err| io::Error::new(err.kind(), format!("{}: {}", path.display(), err)))?;
+        Ok(format!("model:{:016x}", fnv1a(&bytes)))
+    };
+    Ok(match name {
+        "random" => "random".to_string(),
+        "negamax" => format!("negamax:depth={}", options.depth),
+        "mcts" => format!(
+            "mcts:iterations={},exploration={}",
+            options.iterations, options.exploration
+        ),
+        "nn" | "neatnn" => model(&options.model)?,
+        path => model(Path::new(path))?,
+    })
+}
+
+/// A Glicko-2 rating.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Glicko {
+    pub rating: f64,
+    /// How uncertain the rating is.
+    pub deviation: f64,
+    /// How erratic the player's results are.
+    pub volatility: f64,
+}
+
+impl Default for Glicko {
+    fn default() -> Self {
+        Self {
+            rating: 1500.0,
+            deviation: 350.0,
+            volatility: 0.06,
+        }
+    }
+}
+
+impl Glicko {
+    /// Rates a player after a single game against `opponent`, where
+    /// `score` is `1` for a win, `0.5` for a draw and `0` for a loss.
+    fn update(self, opponent: Glicko, score: f64) -> Self {
+        let (mu, phi, sigma) = (
+            (self.rating - 1500.0) / SCALE,
+            self.deviation / SCALE,
+            self.volatility,
+        );
+        let mu_j = (opponent.rating - 1500.0) / SCALE;
+        let g = 1.0 / (1.0 + 3.0 * (opponent.deviation / SCALE).powi(2) / (PI * PI)).sqrt();
+        let e = 1.0 / (1.0 + (-g * (mu - mu_j)).exp());
+        let v = 1.0 / (g * g * e * (1.0 - e));
+        let improvement = g * (score - e);
+        let delta = v * improvement;
+
+        // Find the new volatility with the Illinois algorithm.
+        let a = (sigma * sigma).ln();
+        let f = |x: f64| {
+            let ex = x.exp();
+            ex * (delta * delta - phi * phi - v - ex) / (2.0 * (phi * phi + v + ex).powi(2))
+                - (x - a) / (TAU * TAU)
+        };
+        let mut low = a;
+        let mut high = if delta * delta > phi * phi + v {
+            (delta * delta - phi * phi - v).ln()
+        } else {
+            let mut k = 1.0;
+            while f(a - k * TAU) < 0.0 {
+                k += 1.0;
+            }
+            a - k * TAU
+        };
+        let (mut f_low, mut f_high) = (f(low), f(high));
+        while (high - low).abs() > 1e-6 {
+            let next = low + (low - high) * f_low / (f_high - f_low);
+            let f_next = f(next);
+            if f_next * f_high <= 0.0 {
+                low = high;
+                f_low = f_high;
+            } else {
+                f_low /= 2.0;
+            }
+            high = next;
+            f_high = f_next;
+        }
+        let sigma = (low / 2.0).exp();
+
+        let phi = 1.0 / (1.0 / (phi * phi + sigma * sigma) + 1.0 / v).sqrt();
+        Self {
+            rating: (mu + phi * phi * improvement) * SCALE + 1500.0,
+            deviation: phi * SCALE,
+            volatility: sigma,
+        }
+    }
+}
+
+/// The ratings of a single player.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Entry {
+    pub identity: String,
+    /// The name the player was last entered under.
+    pub name: String,
+    pub elo: f64,
+    pub glicko: Glicko,
+    pub games: usize,
+}
+
+impl Entry {
+    fn new(identity: &str, name: &str) -> Self {
+        Self {
+            identity: identity.to_string(),
+            name: name.to_string(),
+            elo: 1500.0,
+            glicko: Glicko::default(),
+            games: 0,
+        }
+    }
+}
+
+/// The ratings of every player seen so far.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct Ledger {
+    entries: Vec<Entry>,
+}
+
+impl Ledger {
+    /// Returns every player, best first by Glicko-2 rating.
+    pub fn entries(&self) -> Vec<&Entry> {
+        let mut entries: Vec<&Entry> = self.entries.iter().collect();
+        entries.sort_by(|a, b| b.glicko.rating.total_cmp(&a.glicko.rating));
+        entries
+    }
+
+    fn index(&mut self, identity: &str, name: &str) -> usize {
+        match self.entries.iter().position(|e| e.identity == identity) {
+            Some(i) => {
+                self.entries[i].name = name.to_string();
+                i
+            }
+            None => {
+                self.entries.push(Entry::new(identity, name));
+                self.entries.len() - 1
+            }
+        }
+    }
+
+    /// Rates the players of a tournament, where `identities` gives the
+    /// identity of each name entered. Both ratings are updated after every
+    /// game, with each game being a Glicko-2 rating period of its own.
+    pub fn record(&mut self, pairings: &[Pairing], identities: &[(String, String)]) {
+        let identity = |name: &str| -> &str {
+            identities
+                .iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, id)| id.as_str())
+                .expect("every player has an identity")
+        };
+        let pairs: Vec<(usize, usize, &Pairing)> = pairings
+            .iter()
+            .filter(|p| identity(&p.ai) != identity(&p.opponent))
+            .map(|p| {
+                let a = self.index(identity(&p.ai), &p.ai);
+                let b = self.index(identity(&p.opponent), &p.opponent);
+                (a, b, p)
+            })
+            .collect();
+
+        // Take turns between pairings, as if their games were played side by side.
+        let rounds = pairs
+            .iter()
+            .map(|(_, _, p)| p.games.len())
+            .max()
+            .unwrap_or(0);
+        for round in 0..rounds {
+            for (a, b, pairing) in &pairs {
+                let game = match pairing.games.get(round) {
+                    Some(game) => game,
+                    None => continue,
+                };
+                let (ea, eb) = (self.entries[*a].clone(), self.entries[*b].clone());
+
+                let expected = 1.0 / (1.0 + 10f64.powf((eb.elo - ea.elo) / 400.0));
+                let change = K * (game.score() - expected);
+                self.entries[*a].elo += change;
+                self.entries[*b].elo -= change;
+
+                self.entries[*a].glicko = ea.glicko.update(eb.glicko, game.score());
+                self.entries[*b].glicko = eb.glicko.update(ea.glicko, game.reversed().score());
+
+                self.entries[*a].games += 1;
+                self.entries[*b].games += 1;
+            }
+        }
+    }
+
+    /// Reads the ledger at `path`, which is empty if there is no such file.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let in_file =
+            |err: io::Error| io::Error::new(err.kind(), format!("{}: {}", path.display(), err));
+        match File::open(path) {
+            Ok(file) => Self::read(BufReader::new(file)).map_err(in_file),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(in_file(err)),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        self.write(&mut out)?;
+        out.flush()
+    }
+
+    pub fn write(&self, mut out: impl Write) -> io::Result<()> {
+        writeln!(out, "ax-ratings {}", VERSION)?;
+        for e in &self.entries {
+            writeln!(
+                out,
+                "player {} {} {} {} {} {} {}",
+                e.identity,
+                e.elo,
+                e.glicko.rating,
+                e.glicko.deviation,
+                e.glicko.volatility,
+                e.games,
+                e.name
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Reads a ledger written by [`Ledger::write`].
+    pub fn read(input: impl BufRead) -> io::Result<Self> {
+        let mut lines = input.lines();
+        let header = lines
+            .next()
+            .unwrap_or_else(|| Err(invalid("missing ax-ratings header")))?;
+        match header.strip_prefix("ax-ratings ") {
+            Some(v) if v == VERSION.to_string() => {}
+            Some(v) => return Err(invalid(&format!("ratings version {} is not supported", v))),
+            None => return Err(invalid("missing ax-ratings header")),
+        }
+
+        let mut entries = Vec::new();
+        for line in lines {
+            let line = line?;
+            let fields: Vec<&str> = line.splitn(8, ' ').collect();
+            let number = |i: usize| -> io::Result<f64> {
+                fields[i].parse().map_err(|_| invalid("invalid rating"))
+            };
+            if fields.len() != 8 || fields[0] != "player" {
+                return Err(invalid(&format!("expected a player, found {:?}", line)));
+            }
+            entries.push(Entry {
+                identity: fields[1].to_string(),
+                elo: number(2)?,
+                glicko: Glicko {
+                    rating: number(3)?,
+                    deviation: number(4)?,
+                    volatility: number(5)?,
+                },
+                games: fields[6]
+                    .parse()
+                    .map_err(|_| invalid("invalid game count"))?,
+                name: fields[7].to_string(),
+            });
+        }
+        Ok(Self { entries })
+    }
+}
+
+/// Writes the ratings as a table, best first.
+pub fn write_table(ledger: &Ledger, mut out: impl Write) -> io::Result<()> {
+    let entries = ledger.entries();
+    let width = entries
+        .iter()
+        .map(|e| e.name.len())
+        .max()
+        .unwrap_or(0)
+        .max("player".len());
+    writeln!(
+        out,
+        "{:>4}  {:w$}  {:>6}  {:>13}  {:>5}  identity",
+        "rank",
+        "player",
+        "elo",
+        "glicko-2",
+        "games",
+        w = width
+    )?;
+    for (rank, e) in entries.iter().enumerate() {
+        writeln!(
+            out,
+            "{:>4}  {:w$}  {:>6.0}  {:>6.0} ± {:>4.0}  {:>5}  {}",
+            rank + 1,
+            e.name,
+            e.elo,
+            e.glicko.rating,
+            2.0 * e.glicko.deviation,
+            e.games,
+            e.identity,
+            w = width
+        )?;
+    }
+    Ok(())
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
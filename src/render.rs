@@ -0,0 +1,115 @@
+//! Views of game states, for people or for other programs.
+
+use std::error;
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+
+/// How a state is rendered.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Style {
+    /// Plain text, over as many lines as it takes.
+    Plain,
+    /// Text coloured with ANSI escape codes, highlighting e.g. the last move
+    /// made and how the game was won.
+    Ansi,
+    /// A single line of text.
+    Compact,
+    /// A single line of JSON.
+    Json,
+}
+
+#[derive(Debug)]
+pub struct UnknownStyle(String);
+
+impl fmt::Display for UnknownStyle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown style {:?}", self.0)
+    }
+}
+
+impl error::Error for UnknownStyle {}
+
+impl FromStr for Style {
+    type Err = UnknownStyle;
+
+    fn from_str(s: &str) -> Result<Self, UnknownStyle> {
+        match s {
+            "plain" => Ok(Style::Plain),
+            "ansi" => Ok(Style::Ansi),
+            "compact" => Ok(Style::Compact),
+            "json" => Ok(Style::Json),
+            _ => Err(UnknownStyle(s.to_string())),
+        }
+    }
+}
+
+/// Render
+///
+/// Writes a view of a state in any [`Style`]. Every style ends with a
+/// newline, so states rendered one after another each start on a line of
+/// their own.
+pub trait Render {
+    fn render(&self, style: Style, out: &mut dyn fmt::Write) -> fmt::Result;
+
+    /// Renders into an [`io::Write`] instead of a [`fmt::Write`].
+    fn render_io(&self, style: Style, out: &mut dyn io::Write) -> io::Result<()> {
+        let mut adapter = Adapter { out, error: None };
+        match self.render(style, &mut adapter) {
+            Ok(()) => Ok(()),
+            Err(fmt::Error) => Err(adapter
+                .error
+                .unwrap_or_else(|| io::Error::other("failed to render the state"))),
+        }
+    }
+
+    /// Returns the state rendered into a string.
+    fn to_rendered(&self, style: Style) -> String {
+        let mut s = String::new();
+        self.render(style, &mut s)
+            .expect("rendering into a string can't fail");
+        s
+    }
+}
+
+/// Writes into an [`io::Write`], keeping the error `fmt::Write` has no room for.
+struct Adapter<'a> {
+    out: &'a mut dyn io::Write,
+    error: Option<io::Error>,
+}
+
+impl fmt::Write for Adapter<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.out.write_all(s.as_bytes()).map_err(|err| {
+            self.error = Some(err);
+            fmt::Error
+        })
+    }
+}
+
+/// Writes `s` as a JSON string, quoted and escaped.
+pub fn json_string(out: &mut dyn fmt::Write, s: &str) -> fmt::Result {
+    out.write_char('"')?;
+    for c in s.chars() {
+        match c {
+            '"' => out.write_str("\\\"")?,
+            '\\' => out.write_str("\\\\")?,
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32)?,
+            c => out.write_char(c)?,
+        }
+    }
+    out.write_char('"')
+}
+
+/// ANSI escape codes used by [`Style::Ansi`].
+pub mod ansi {
+    pub const RESET: &str = "\x1b[0m";
+    pub const BOLD: &str = "\x1b[1m";
+    pub const DIM: &str = "\x1b[2m";
+    pub const UNDERLINE: &str = "\x1b[4m";
+    pub const RED: &str = "\x1b[31m";
+    pub const GREEN: &str = "\x1b[32m";
+    pub const YELLOW: &str = "\x1b[33m";
+    pub const BLUE: &str = "\x1b[34m";
+    pub const ON_GREEN: &str = "\x1b[42m";
+}
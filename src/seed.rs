@@ -0,0 +1,165 @@
+//! Reproducible randomness.
+//!
+//! Every stochastic part of a run, such as setting up a game or an AI
+//! picking its moves, draws from its own stream of random numbers. Each
+//! stream is derived from a single seed and the name of the stream, so runs
+//! with the same seed give the same output, and adding a stream doesn't
+//! change any of the others.
+//!
+//! The streams of a game are handed out by [`Streams`], which keeps track
+//! of how far each of them got, so they can be saved with the game and
+//! picked up where they left off.
+
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use rand::{Rng, RngCore, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+
+/// The seed every stream of a run is derived from.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Seed(u64);
+
+impl Seed {
+    /// Uses `seed`, or picks one at random if there is none.
+    pub fn new(seed: Option<u64>) -> Self {
+        Seed(seed.unwrap_or_else(|| rand::thread_rng().gen()))
+    }
+
+    /// Returns the stream of random numbers called `name`.
+    pub fn stream(self, name: &str) -> Stream {
+        // The hash of the name, mixed into the seed with splitmix64.
+        let mut z = (self.0 ^ fnv1a(name.as_bytes())).wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        Stream {
+            rng: ChaCha12Rng::seed_from_u64(z ^ (z >> 31)),
+            drawn: Arc::default(),
+        }
+    }
+
+    /// Returns a seed of its own for a part of the run called `name`, e.g.
+    /// a single game of a tournament, from which it can derive streams.
+    pub fn derive(self, name: &str) -> Seed {
+        Seed(self.stream(name).gen())
+    }
+}
+
+/// The 64 bit FNV-1a hash of `bytes`, which is the same on every run and
+/// platform unlike the hashers of the standard library.
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |h: u64, b| {
+        (h ^ u64::from(*b)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+impl fmt::Display for Seed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// How many words a stream has drawn, shared with [`Streams`].
+type Drawn = Arc<AtomicU64>;
+
+/// A stream of random numbers, which counts the 32 bit words it has drawn.
+#[derive(Debug)]
+pub struct Stream {
+    rng: ChaCha12Rng,
+    drawn: Drawn,
+}
+
+impl Stream {
+    /// Returns how many words have been drawn.
+    pub fn drawn(&self) -> u64 {
+        self.drawn.load(Ordering::Relaxed)
+    }
+
+    fn count(&mut self) {
+        self.drawn
+            .store(self.rng.get_word_pos() as u64, Ordering::Relaxed);
+    }
+}
+
+impl RngCore for Stream {
+    fn next_u32(&mut self) -> u32 {
+        let n = self.rng.next_u32();
+        self.count();
+        n
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let n = self.rng.next_u64();
+        self.count();
+        n
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest);
+        self.count();
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.rng.try_fill_bytes(dest)?;
+        self.count();
+        Ok(())
+    }
+}
+
+/// Hands out the streams of a seed, keeping track of how many words each
+/// of them has drawn. Clones share the streams they keep track of.
+#[derive(Debug, Clone)]
+pub struct Streams {
+    seed: Seed,
+    drawn: Arc<Mutex<Vec<(String, Drawn)>>>,
+}
+
+impl Streams {
+    pub fn new(seed: Seed) -> Self {
+        Self {
+            seed,
+            drawn: Arc::default(),
+        }
+    }
+
+    pub fn seed(&self) -> Seed {
+        self.seed
+    }
+
+    /// Returns the stream called `name`, carrying on from where it was
+    /// if it was handed out or [resumed](Streams::resume) before.
+    pub fn stream(&self, name: &str) -> Stream {
+        let mut stream = self.seed.stream(name);
+        let mut drawn = self.drawn.lock().unwrap();
+        match drawn.iter().find(|(n, _)| n == name) {
+            Some((_, words)) => {
+                stream
+                    .rng
+                    .set_word_pos(u128::from(words.load(Ordering::Relaxed)));
+                stream.drawn = Arc::clone(words);
+            }
+            None => drawn.push((name.to_string(), Arc::clone(&stream.drawn))),
+        }
+        stream
+    }
+
+    /// Picks the stream called `name` up after `words` words were drawn.
+    pub fn resume(&self, name: &str, words: u64) {
+        let mut drawn = self.drawn.lock().unwrap();
+        match drawn.iter().find(|(n, _)| n == name) {
+            Some((_, drawn)) => drawn.store(words, Ordering::Relaxed),
+            None => drawn.push((name.to_string(), Arc::new(AtomicU64::new(words)))),
+        }
+    }
+
+    /// Returns how many words each stream handed out has drawn so far.
+    pub fn drawn(&self) -> Vec<(String, u64)> {
+        self.drawn
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(name, words)| (name.clone(), words.load(Ordering::Relaxed)))
+            .collect()
+    }
+}
@@ -0,0 +1,326 @@
+//! Snapshots of games in progress, so they can be resumed later.
+//!
+//! A snapshot is a header line followed by `key values...` lines. Every
+//! snapshot names its game, the seed of the run, how many words each
+//! [stream](crate::seed::Streams) of random numbers has drawn and whose turn
+//! it is, and the rest depends on the game:
+//!
+//! ```text
+//! ax-snapshot 2
+//! game tic-tac-toe
+//! seed 42
+//! rng 0 tic-tac-toe seat 0
+//! rng 36 tic-tac-toe seat 1
+//! turn 1
+//! moves 4 0 8
+//! ```
+//!
+//! A resumed game picks every stream up where it left off, so it plays out
+//! the same way as if it had never been saved.
+
+use std::error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use crate as ax;
+use crate::numberguesser;
+use crate::seed::{Seed, Streams};
+use crate::tictactoe::Board;
+use crate::{Game, State};
+
+/// The version of the format written by [`write`].
+pub const VERSION: u32 = 2;
+
+/// A game whose position can be written as [`Fields`], as snapshots and
+/// [transcripts](crate::transcript) both do.
+pub trait Position: Sized {
+    /// The name of the game, which a snapshot or transcript has to match to
+    /// be read.
+    const GAME: &'static str;
+
+    /// Returns how many moves have been made.
+    fn moves(&self) -> usize;
+
+    /// Returns the values saved under each key.
+    fn fields(&self) -> Vec<(&'static str, Vec<i64>)>;
+
+    /// Rebuilds a saved game from its fields, where `self` is a new game
+    /// giving anything the fields leave out, like the pieces used.
+    fn restore(self, fields: &Fields) -> Result<Self, String>;
+}
+
+/// A game which can be saved in a snapshot.
+pub trait Snapshot: Position {
+    /// Returns the seat of the player whose turn it is.
+    fn turn(&self) -> usize;
+}
+
+/// The values of a snapshot, by key.
+#[derive(Debug, Default, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Fields(Vec<(String, Vec<i64>)>);
+
+impl Fields {
+    /// Returns the fields `state` is saved with.
+    pub fn of<S: Position>(state: &S) -> Self {
+        Fields(
+            state
+                .fields()
+                .into_iter()
+                .map(|(key, values)| (key.to_string(), values))
+                .collect(),
+        )
+    }
+
+    /// Writes a `key values...` line for each field.
+    pub fn write(&self, mut out: impl Write) -> io::Result<()> {
+        for (key, values) in &self.0 {
+            write!(out, "{}", key)?;
+            for value in values {
+                write!(out, " {}", value)?;
+            }
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+
+    /// Adds a field read from a `key values...` line.
+    pub fn parse(&mut self, line: &str) -> Result<(), String> {
+        let mut words = line.split_whitespace();
+        let key = words.next().ok_or_else(|| "missing key".to_string())?;
+        let values = words
+            .map(|s| s.parse().map_err(|_| format!("invalid number {:?}", s)))
+            .collect::<Result<_, _>>()?;
+        self.0.push((key.to_string(), values));
+        Ok(())
+    }
+
+    pub fn get(&self, key: &str) -> Result<&[i64], String> {
+        self.0
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, values)| values.as_slice())
+            .ok_or_else(|| format!("missing {}", key))
+    }
+
+    /// Returns the value of a key holding a single value.
+    pub fn one(&self, key: &str) -> Result<i64, String> {
+        match self.get(key)? {
+            [value] => Ok(*value),
+            _ => Err(format!("expected a single value for {}", key)),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    /// The snapshot is unreadable or describes an impossible game.
+    Corrupt(String),
+    /// The snapshot is of a different game.
+    Mismatch {
+        expected: &'static str,
+        found: String,
+    },
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "{}", err),
+            Error::Corrupt(msg) => write!(f, "corrupt snapshot: {}", msg),
+            Error::Mismatch { expected, found } => {
+                write!(f, "this is a snapshot of {}, not {}", found, expected)
+            }
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+pub fn save<S: Snapshot>(path: &Path, state: &S, streams: &Streams) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    write(&mut out, state, streams)?;
+    out.flush()
+}
+
+pub fn write<S: Snapshot>(mut out: impl Write, state: &S, streams: &Streams) -> io::Result<()> {
+    writeln!(out, "ax-snapshot {}", VERSION)?;
+    writeln!(out, "game {}", S::GAME)?;
+    writeln!(out, "seed {}", streams.seed())?;
+    for (name, words) in streams.drawn() {
+        writeln!(out, "rng {} {}", words, name)?;
+    }
+    writeln!(out, "turn {}", state.turn())?;
+    Fields::of(state).write(out)
+}
+
+/// Loads a game saved with [`save`], along with the streams to resume it with.
+pub fn load<S: Snapshot>(path: &Path, new: S) -> Result<(S, Streams), Error> {
+    read(BufReader::new(File::open(path)?), new)
+}
+
+/// Reads a game written by [`write`], see [`load`].
+pub fn read<S: Snapshot>(input: impl BufRead, new: S) -> Result<(S, Streams), Error> {
+    let mut lines = input.lines();
+    match lines.next().transpose()? {
+        Some(line) if line == format!("ax-snapshot {}", VERSION) => {}
+        Some(line) if line.starts_with("ax-snapshot ") => {
+            return Err(Error::Corrupt(format!("{} is not supported", line)))
+        }
+        _ => return Err(Error::Corrupt("missing ax-snapshot header".to_string())),
+    }
+
+    let mut game = None;
+    let mut seed = None;
+    let mut drawn = Vec::new();
+    let mut fields = Fields::default();
+    for (i, line) in lines.enumerate() {
+        let line = line?;
+        let at = |msg: String| Error::Corrupt(format!("line {}: {}", i + 2, msg));
+        let mut words = line.split_whitespace();
+        let key = match words.next() {
+            Some(key) => key,
+            None => continue,
+        };
+        match key {
+            "game" => game = words.next().map(str::to_string),
+            "seed" => {
+                let value = words.next().and_then(|s| s.parse().ok());
+                seed = Some(value.ok_or_else(|| at("invalid seed".to_string()))?);
+            }
+            "rng" => {
+                let count = words.next().and_then(|s| s.parse().ok());
+                let name = words.collect::<Vec<_>>().join(" ");
+                match count {
+                    Some(n) if !name.is_empty() => drawn.push((name, n)),
+                    _ => return Err(at("invalid rng".to_string())),
+                }
+            }
+            _ => fields.parse(&line).map_err(at)?,
+        }
+    }
+
+    match game {
+        Some(game) if game == S::GAME => {}
+        Some(found) => {
+            return Err(Error::Mismatch {
+                expected: S::GAME,
+                found,
+            })
+        }
+        None => return Err(Error::Corrupt("missing game".to_string())),
+    }
+    let seed = seed.ok_or_else(|| Error::Corrupt("missing seed".to_string()))?;
+    let turn = fields.one("turn").map_err(Error::Corrupt)?;
+
+    let state = new.restore(&fields).map_err(Error::Corrupt)?;
+    if turn != state.turn() as i64 {
+        return Err(Error::Corrupt(format!(
+            "it should be seat {}'s turn, but the moves make it seat {}'s",
+            turn,
+            state.turn()
+        )));
+    }
+    let streams = Streams::new(Seed::new(Some(seed)));
+    for (name, words) in drawn {
+        streams.resume(&name, words);
+    }
+    Ok((state, streams))
+}
+
+impl<P> Snapshot for Board<P>
+where
+    P: PartialEq + Default + Copy,
+{
+    fn turn(&self) -> usize {
+        self.current_player()
+    }
+}
+
+impl<P> Position for Board<P>
+where
+    P: PartialEq + Default + Copy,
+{
+    const GAME: &'static str = "tic-tac-toe";
+
+    fn moves(&self) -> usize {
+        Board::moves(self).len()
+    }
+
+    fn fields(&self) -> Vec<(&'static str, Vec<i64>)> {
+        vec![(
+            "moves",
+            Board::moves(self).into_iter().map(|i| i as i64).collect(),
+        )]
+    }
+
+    fn restore(mut self, fields: &Fields) -> Result<Self, String> {
+        for (i, mv) in fields.get("moves")?.iter().enumerate() {
+            if *mv < 0 || !self.is_legal(&(*mv as usize)) {
+                return Err(format!("move {} at {} is not legal", i + 1, mv));
+            }
+            self.apply(*mv as usize);
+        }
+        if let Ok(ax::Status::Terminal(_)) = self.status() {
+            return Err("the game is already over".to_string());
+        }
+        Ok(self)
+    }
+}
+
+impl Snapshot for numberguesser::State {
+    fn turn(&self) -> usize {
+        self.current_player()
+    }
+}
+
+impl Position for numberguesser::State {
+    const GAME: &'static str = "number-guesser";
+
+    fn moves(&self) -> usize {
+        self.history().len()
+    }
+
+    fn fields(&self) -> Vec<(&'static str, Vec<i64>)> {
+        let (low, high) = self.range();
+        vec![
+            ("range", vec![low, high]),
+            ("number", vec![self.number()]),
+            (
+                "guesses",
+                self.history().into_iter().map(|(guess, _)| guess).collect(),
+            ),
+        ]
+    }
+
+    fn restore(self, fields: &Fields) -> Result<Self, String> {
+        let (low, high) = match fields.get("range")? {
+            [low, high] if low < high => (*low, *high),
+            _ => return Err("invalid range".to_string()),
+        };
+        let number = fields.one("number")?;
+        if !(low..high).contains(&number) {
+            return Err(format!("{} is out of range", number));
+        }
+        let mut state = numberguesser::State::with_number(low, high, number);
+        for guess in fields.get("guesses")? {
+            if !state.is_legal(guess) {
+                return Err(format!("the guess {} is not legal", guess));
+            }
+            state.apply(*guess);
+        }
+        if state.last_guess() == Some(number) {
+            return Err("the game is already over".to_string());
+        }
+        Ok(state)
+    }
+}
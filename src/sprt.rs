@@ -0,0 +1,206 @@
+//! Tell whether a change to an AI made it stronger with a sequential
+//! probability ratio test.
+//!
+//! A candidate plays pairs of games against a baseline, taking turns to move
+//! first, until the log-likelihood ratio (LLR) of the hypotheses
+//!
+//! - H0: the candidate is `elo0` stronger than the baseline, and
+//! - H1: the candidate is `elo1` stronger than the baseline
+//!
+//! crosses one of the bounds set by the error rates `alpha` and `beta`. The
+//! LLR uses the normal approximation to the results of a game. Every record
+//! starts with half a win and half a loss, so one which holds a single kind
+//! of result, like a candidate winning every game, still has a variance and
+//! can reach a verdict.
+
+use std::cmp::Ordering;
+use std::error;
+use std::fmt;
+
+use crate::players::Options;
+use crate::tournament::{self, Record, Tournament};
+
+/// Wins and losses every record starts with before its LLR is worked out.
+const PRIOR: f64 = 0.5;
+
+/// The hypotheses tested and the error rates allowed.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Bounds {
+    pub elo0: f64,
+    pub elo1: f64,
+    /// Chance of accepting H1 when H0 is true.
+    pub alpha: f64,
+    /// Chance of accepting H0 when H1 is true.
+    pub beta: f64,
+}
+
+impl Bounds {
+    /// Checks H1 is the stronger hypothesis and both error rates are chances.
+    pub fn check(&self) -> Result<(), String> {
+        if self.elo0.partial_cmp(&self.elo1) != Some(Ordering::Less) {
+            return Err(format!(
+                "elo0 ({}) must be less than elo1 ({})",
+                self.elo0, self.elo1
+            ));
+        }
+        for (name, rate) in &[("alpha", self.alpha), ("beta", self.beta)] {
+            if !(0.0 < *rate && *rate < 1.0) {
+                return Err(format!("{} ({}) must lie between 0 and 1", name, rate));
+            }
+        }
+        Ok(())
+    }
+
+    /// H0 is accepted once the LLR falls to this.
+    pub fn lower(&self) -> f64 {
+        (self.beta / (1.0 - self.alpha)).ln()
+    }
+
+    /// H1 is accepted once the LLR rises to this.
+    pub fn upper(&self) -> f64 {
+        ((1.0 - self.beta) / self.alpha).ln()
+    }
+
+    /// Returns the log-likelihood ratio of H1 to H0 given `record`.
+    pub fn llr(&self, record: &Record) -> f64 {
+        let (wins, draws, losses) = (
+            record.wins as f64 + PRIOR,
+            record.draws as f64,
+            record.losses as f64 + PRIOR,
+        );
+        let n = wins + draws + losses;
+        let score = (wins + draws / 2.0) / n;
+        let variance =
+            (wins * (1.0 - score).powi(2) + draws * (0.5 - score).powi(2) + losses * score.powi(2))
+                / n;
+        let (s0, s1) = (expected_score(self.elo0), expected_score(self.elo1));
+        n * (s1 - s0) * (2.0 * score - s0 - s1) / (2.0 * variance)
+    }
+
+    pub fn verdict(&self, llr: f64) -> Option<Verdict> {
+        if llr >= self.upper() {
+            Some(Verdict::H1)
+        } else if llr <= self.lower() {
+            Some(Verdict::H0)
+        } else {
+            None
+        }
+    }
+}
+
+/// Returns the share of points expected by a player `elo` stronger than its opponent.
+pub fn expected_score(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+/// Returns how much stronger a player scoring `score` is than its opponent.
+pub fn elo(score: f64) -> f64 {
+    let score = score.clamp(1e-6, 1.0 - 1e-6);
+    400.0 * (score / (1.0 - score)).log10()
+}
+
+/// Which hypothesis was accepted.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Verdict {
+    H0,
+    H1,
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Verdict::H0 => write!(f, "H0"),
+            Verdict::H1 => write!(f, "H1"),
+        }
+    }
+}
+
+/// Why a test couldn't be run.
+#[derive(Debug)]
+pub enum Error {
+    /// The bounds don't make a test, see [`Bounds::check`].
+    Bounds(String),
+    /// The games couldn't be played.
+    Games(tournament::Error),
+}
+
+impl From<tournament::Error> for Error {
+    fn from(err: tournament::Error) -> Self {
+        Error::Games(err)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Bounds(reason) => write!(f, "{}", reason),
+            Error::Games(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Bounds(_) => None,
+            Error::Games(err) => Some(err),
+        }
+    }
+}
+
+/// Where a test stands after a pair of games.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Progress {
+    /// The results of the candidate.
+    pub record: Record,
+    pub llr: f64,
+    /// The accepted hypothesis, if the test is over.
+    pub verdict: Option<Verdict>,
+}
+
+/// Plays `candidate` against `baseline` until a hypothesis is accepted, or
+/// `max_games` games have been played.
+#[derive(Debug, Clone)]
+pub struct Sprt {
+    pub baseline: String,
+    pub candidate: String,
+    pub bounds: Bounds,
+    pub max_games: usize,
+    pub options: Options,
+}
+
+impl Sprt {
+    /// Runs the test, calling `progress` after every pair of games, and after
+    /// the last game if `max_games` is odd.
+    pub fn run(&self, mut progress: impl FnMut(&Progress)) -> Result<Progress, Error> {
+        self.bounds.check().map_err(Error::Bounds)?;
+        let games = Tournament {
+            ais: vec![self.candidate.clone(), self.baseline.clone()],
+            games: self.max_games,
+            threads: 1,
+            options: self.options.clone(),
+        };
+        games.check()?;
+
+        let mut state = Progress {
+            record: Record::default(),
+            llr: 0.0,
+            verdict: None,
+        };
+        let mut game = 0;
+        while game < self.max_games {
+            let end = (game + 2).min(self.max_games);
+            while game < end {
+                state.record.add(games.play((0, 1), game)?);
+                game += 1;
+            }
+            state.llr = self.bounds.llr(&state.record);
+            state.verdict = self.bounds.verdict(state.llr);
+            progress(&state);
+            if state.verdict.is_some() {
+                break;
+            }
+        }
+        Ok(state)
+    }
+}
@@ -1,67 +1,183 @@
-use std::cell::RefCell;
 use std::fmt;
 use std::io::{self, Write};
 use std::ops::Index;
-
-use ax;
+use std::path::Path;
 
 use rand::Rng;
 
-#[derive(Debug, PartialEq)]
+use crate as ax;
+use crate::render::{self, ansi, Render, Style};
+use crate::{Game, State};
+
+/// A tic-tac-toe board, where players place pieces of type `P`.
+///
+/// With the `serde` feature a board is serialized as its players and moves,
+/// like a snapshot, so only legal boards are deserialized and their hash is
+/// recomputed rather than trusted.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(
+        try_from = "Moves<P>",
+        into = "Moves<P>",
+        bound(
+            serialize = "P: serde::Serialize + Copy",
+            deserialize = "P: serde::Deserialize<'de> + PartialEq + Default + Copy"
+        )
+    )
+)]
 pub struct Board<P> {
     def: P,
+    players: [P; 2],
     pieces: Vec<(usize, P)>,
+    hash: u64,
 }
 
 impl<P: Default> Board<P> {
-    pub fn new() -> Self {
+    /// Creates an empty board where the player using `first` goes first.
+    pub fn new(first: P, second: P) -> Self {
         Self {
             def: P::default(),
+            players: [first, second],
             pieces: Vec::with_capacity(9),
+            hash: 0,
         }
     }
 }
 
-impl<P, U> From<U> for Board<P>
-where
-    P: Default,
-    U: Into<Vec<(usize, P)>>,
-{
-    fn from(pieces: U) -> Self {
+/// How a [`Board`] is serialized.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Moves<P> {
+    players: [P; 2],
+    moves: Vec<usize>,
+}
+
+#[cfg(feature = "serde")]
+impl<P: Copy> From<Board<P>> for Moves<P> {
+    fn from(board: Board<P>) -> Self {
         Self {
-            def: P::default(),
-            pieces: pieces.into(),
+            players: board.players,
+            moves: board.pieces.iter().map(|(i, _)| *i).collect(),
         }
     }
 }
 
-impl<P: PartialEq> Board<P> {
-    pub fn is_winner(&self, piece: P) -> Option<bool> {
-        if self[0] == self[1] && self[1] == self[2] {
-            return Some(self[2] == piece);
-        }
-        if self[3] == self[4] && self[4] == self[5] {
-            return Some(self[5] == piece);
-        }
-        if self[6] == self[7] && self[7] == self[8] {
-            return Some(self[8] == piece);
-        }
-        if self[0] == self[3] && self[3] == self[6] {
-            return Some(self[6] == piece);
-        }
-        if self[1] == self[4] && self[4] == self[7] {
-            return Some(self[7] == piece);
-        }
-        if self[2] == self[5] && self[5] == self[8] {
-            return Some(self[8] == piece);
+#[cfg(feature = "serde")]
+impl<P> std::convert::TryFrom<Moves<P>> for Board<P>
+where
+    P: PartialEq + Default + Copy,
+{
+    type Error = String;
+
+    fn try_from(saved: Moves<P>) -> Result<Self, String> {
+        let [first, second] = saved.players;
+        if first == second || first == P::default() || second == P::default() {
+            return Err("the players need pieces of their own".to_string());
         }
-        if self[0] == self[4] && self[4] == self[8] {
-            return Some(self[8] == piece);
+        let mut board = Board::new(first, second);
+        for (i, mv) in saved.moves.into_iter().enumerate() {
+            if !board.is_legal(&mv) {
+                return Err(format!("move {} at {} is not legal", i + 1, mv));
+            }
+            board.apply(mv);
         }
-        if self[2] == self[4] && self[4] == self[6] {
-            return Some(self[6] == piece);
+        Ok(board)
+    }
+}
+
+/// Random keys for Zobrist hashing, one for each position and seat.
+const ZOBRIST: [[u64; 2]; 9] = zobrist_keys();
+
+const fn zobrist_keys() -> [[u64; 2]; 9] {
+    // splitmix64, with a fixed seed so hashes are the same on every run.
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut keys = [[0; 2]; 9];
+    let mut i = 0;
+    while i < 18 {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        keys[i / 2][i % 2] = z ^ (z >> 31);
+        i += 1;
+    }
+    keys
+}
+
+/// How many values [`Board::features`] describes a board with.
+pub const FEATURES: usize = 18;
+
+/// Every row, column and diagonal of the board.
+const LINES: [[usize; 3]; 8] = [
+    [0, 1, 2],
+    [3, 4, 5],
+    [6, 7, 8],
+    [0, 3, 6],
+    [1, 4, 7],
+    [2, 5, 8],
+    [0, 4, 8],
+    [2, 4, 6],
+];
+
+impl<P: PartialEq> Board<P> {
+    /// Returns the piece which completed a line, if any.
+    pub fn winner(&self) -> Option<&P> {
+        self.winning_line().map(|line| &self[line[0]])
+    }
+
+    /// Returns the positions of the line which won the game, if any.
+    pub fn winning_line(&self) -> Option<[usize; 3]> {
+        LINES.iter().copied().find(|l| {
+            let (a, b, c) = (&self[l[0]], &self[l[1]], &self[l[2]]);
+            *a != self.def && a == b && b == c
+        })
+    }
+
+    /// Returns the seat of the player using `piece`, i.e. `0` for
+    /// whoever places the first piece and `1` for their opponent.
+    pub fn seat(&self, piece: &P) -> Option<usize> {
+        self.players.iter().position(|p| p == piece)
+    }
+
+    /// Describes the board from the point of view of `seat`, for use as the
+    /// input of a nueral network. The first nine values mark the positions
+    /// holding that player's pieces, the other nine their opponent's.
+    pub fn features(&self, seat: usize) -> Vec<f64> {
+        let mut features = vec![0.0; FEATURES];
+        for (i, piece) in &self.pieces {
+            let offset = if self.seat(piece) == Some(seat) { 0 } else { 9 };
+            features[offset + i] = 1.0;
         }
-        None
+        features
+    }
+
+    /// Scores the board for `seat` by how many lines are still open to
+    /// them, less how many are still open to their opponent.
+    pub fn hueristic(&self, seat: usize) -> i8 {
+        LINES
+            .iter()
+            .map(|line| {
+                let seats: Vec<Option<usize>> = line
+                    .iter()
+                    .filter(|i| self[**i] != self.def)
+                    .map(|i| self.seat(&self[*i]))
+                    .collect();
+                let mine = seats.iter().all(|s| *s == Some(seat));
+                let theirs = seats.iter().all(|s| *s != Some(seat));
+                match (mine, theirs) {
+                    (true, false) => 1,
+                    (false, true) => -1,
+                    _ => 0,
+                }
+            })
+            .sum()
+    }
+
+    /// Returns where each piece was placed, in the order they were placed.
+    pub fn moves(&self) -> Vec<usize> {
+        self.pieces.iter().map(|(i, _)| *i).collect()
     }
 
     pub fn has_empty(&self) -> bool {
@@ -75,20 +191,43 @@ impl<P: PartialEq> Board<P> {
             || self[7] == self.def
             || self[8] == self.def
     }
+}
 
-    pub fn place_piece(&mut self, index: usize, piece: P) -> Result<(), Error> {
+impl<P: PartialEq + Copy> Board<P> {
+    /// Returns the piece of the player whose turn it is.
+    pub fn next_piece(&self) -> P {
+        self.players[self.current_player()]
+    }
+
+    /// Places the next piece at `index`.
+    pub fn place_piece(&mut self, index: usize) -> Result<(), Error> {
+        if index >= 9 {
+            return Err(Error::OutOfRange(index));
+        }
         for (i, _) in &self.pieces {
             if *i == index {
                 return Err(Error::SpotOccupied);
             }
         }
-        self.pieces.push((index, piece));
+        self.hash ^= ZOBRIST[index][self.current_player()];
+        self.pieces.push((index, self.next_piece()));
         Ok(())
     }
 }
 
+#[derive(Debug)]
 pub enum Error {
     SpotOccupied,
+    OutOfRange(usize),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::SpotOccupied => write!(f, "that spot is already taken"),
+            Error::OutOfRange(i) => write!(f, "{} is not a position, pick one from 0 to 8", i),
+        }
+    }
 }
 
 impl<P> fmt::Display for Board<P>
@@ -104,58 +243,155 @@ where
     }
 }
 
-impl<P> ax::AsBytes for Board<P>
+impl<P> Render for Board<P>
 where
-    P: fmt::Display,
+    P: fmt::Display + PartialEq,
 {
-    fn as_bytes(&self) -> Vec<u8> {
-        let mut s = String::new();
-        fmt::write(&mut s, format_args!("{}", self)).expect("unexpected error");
-        s.into_bytes()
+    fn render(&self, style: Style, out: &mut dyn fmt::Write) -> fmt::Result {
+        let last = self.pieces.last().map(|(i, _)| *i);
+        let line = self.winning_line();
+        match style {
+            Style::Plain => write!(out, "{}", self),
+            Style::Ansi => {
+                for row in 0..3 {
+                    if row > 0 {
+                        writeln!(out, "---------")?;
+                    }
+                    for i in row * 3..row * 3 + 3 {
+                        if i % 3 > 0 {
+                            write!(out, " | ")?;
+                        }
+                        let piece = &self[i];
+                        if *piece == self.def {
+                            write!(out, "{}{}{}", ansi::DIM, i, ansi::RESET)?;
+                            continue;
+                        }
+                        let colour = match self.seat(piece) {
+                            Some(0) => ansi::BLUE,
+                            _ => ansi::RED,
+                        };
+                        let highlight = match (line, last) {
+                            (Some(line), _) if line.contains(&i) => ansi::ON_GREEN,
+                            (_, Some(last)) if last == i => ansi::UNDERLINE,
+                            _ => "",
+                        };
+                        write!(
+                            out,
+                            "{}{}{}{}{}",
+                            ansi::BOLD,
+                            colour,
+                            highlight,
+                            piece,
+                            ansi::RESET
+                        )?;
+                    }
+                    writeln!(out)?;
+                }
+                Ok(())
+            }
+            Style::Compact => {
+                for i in 0..9 {
+                    if i > 0 && i % 3 == 0 {
+                        write!(out, "/")?;
+                    }
+                    match &self[i] {
+                        piece if *piece == self.def => write!(out, ".")?,
+                        piece => write!(out, "{}", piece)?,
+                    }
+                }
+                writeln!(out)
+            }
+            Style::Json => {
+                write!(out, "{{\"cells\":[")?;
+                for i in 0..9 {
+                    if i > 0 {
+                        write!(out, ",")?;
+                    }
+                    match &self[i] {
+                        piece if *piece == self.def => write!(out, "null")?,
+                        piece => render::json_string(out, &piece.to_string())?,
+                    }
+                }
+                write!(out, "],\"turn\":{}", self.current_player())?;
+                match last {
+                    Some(i) => write!(out, ",\"last\":{}", i)?,
+                    None => write!(out, ",\"last\":null")?,
+                }
+                match (line, self.winner().and_then(|p| self.seat(p))) {
+                    (Some(line), Some(seat)) => write!(
+                        out,
+                        ",\"winner\":{},\"line\":[{},{},{}]",
+                        seat, line[0], line[1], line[2]
+                    )?,
+                    _ => write!(out, ",\"winner\":null,\"line\":null")?,
+                }
+                writeln!(out, ",\"over\":{}}}", line.is_some() || !self.has_empty())
+            }
+        }
     }
 }
 
 impl<P> ax::State<()> for Board<P>
 where
-    P: PartialEq + Default,
+    P: PartialEq,
 {
     fn status(&self) -> Result<ax::Status, ()> {
-        let winner = self.is_winner(P::default());
-        if let Some(false) = winner {
-            return Ok(ax::Status::Terminal);
+        if let Some(piece) = self.winner() {
+            let seat = self.seat(piece).ok_or(())?;
+            return Ok(ax::Status::Terminal(ax::Outcome::Winner(seat)));
         }
         if !self.has_empty() {
-            return Ok(ax::Status::Terminal);
+            return Ok(ax::Status::Terminal(ax::Outcome::Draw));
         }
         Ok(ax::Status::Valid)
     }
+
+    fn current_player(&self) -> usize {
+        self.pieces.len() % 2
+    }
 }
 
-impl<P> ax::FiniteState<()> for Board<P>
+impl<P> ax::Game<()> for Board<P>
 where
     P: PartialEq + Default + Copy,
 {
-    fn next_possibilities(&self) -> Option<Vec<Self>>
-    where
-        Self: Sized,
-    {
-        let next_piece = self.pieces[self.pieces.len() - 2].1;
-        Some(
-            self.pieces
-                .iter()
-                .map(|p| p.0)
-                .fold(vec![0, 1, 2, 3, 4, 5, 6, 7, 8], |acc, i| {
-                    acc.into_iter().filter(|n| i != *n).collect::<Vec<usize>>()
-                })
-                .iter()
-                .map(|i| {
-                    let mut v = Vec::with_capacity(9);
-                    v.push((*i, next_piece));
-                    v.extend(self.pieces.clone());
-                    Board::from(v)
-                })
-                .collect(),
-        )
+    type Move = usize;
+
+    fn legal_moves(&self) -> Vec<usize> {
+        if self.winner().is_some() {
+            return Vec::new();
+        }
+        (0..9).filter(|i| self[*i] == self.def).collect()
+    }
+
+    fn apply(&mut self, mv: usize) {
+        self.place_piece(mv).expect("move should be legal");
+    }
+
+    fn is_legal(&self, mv: &usize) -> bool {
+        *mv < 9 && self[*mv] == self.def && self.winner().is_none()
+    }
+
+    fn last_move(&self) -> Option<usize> {
+        self.pieces.last().map(|(i, _)| *i)
+    }
+
+    fn undo(&mut self, mv: usize) {
+        let last = self.pieces.pop();
+        if let Some((i, _)) = last {
+            self.hash ^= ZOBRIST[i][self.current_player()];
+        }
+        debug_assert_eq!(
+            last.map(|(i, _)| i),
+            Some(mv),
+            "can only undo the last move"
+        );
+    }
+}
+
+impl<P> ax::StateHash for Board<P> {
+    fn state_hash(&self) -> u64 {
+        self.hash
     }
 }
 
@@ -170,32 +406,79 @@ impl<P> Index<usize> for Board<P> {
     }
 }
 
+/// Where [`Human`] saves the game if they don't name a file.
+pub const SAVE_FILE: &str = "tic-tac-toe.save";
+
+/// Saves a game to a file.
+type Save<S> = Box<dyn FnMut(&S, &Path) -> io::Result<()>>;
+
 /// Human
-pub struct Human<P>(pub P);
+///
+/// Besides a position, they can enter `save [FILE]` to save the game, after
+/// which they are asked to move again.
+pub struct Human<P> {
+    save: Option<Save<Board<P>>>,
+}
+
+impl<P> Default for Human<P> {
+    fn default() -> Self {
+        Self { save: None }
+    }
+}
+
+impl<P> Human<P> {
+    /// Lets the human save the game with `save`.
+    pub fn on_save(
+        mut self,
+        save: impl FnMut(&Board<P>, &Path) -> io::Result<()> + 'static,
+    ) -> Self {
+        self.save = Some(Box::new(save));
+        self
+    }
+}
 
 impl<P> ax::Player<Board<P>> for Human<P>
 where
     P: fmt::Display + PartialEq + Default + Copy,
 {
-    fn take_turn(&mut self, board: Board<P>) -> Board<P> {
+    fn take_turn(&mut self, board: Board<P>) -> Result<Board<P>, ax::PlayerError<Board<P>>> {
         let mut input = String::new();
+        loop {
+            io::stdout().write_all(b"Enter a position: ")?;
+            io::stdout().flush()?;
+            input.clear();
+            if io::stdin().read_line(&mut input)? == 0 {
+                return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+            }
 
-        io::stdout()
-            .write_all(b"Enter a position: ")
-            .expect("failed to write input message");
-        io::stdout().flush().expect("failed to flush input message");
-        io::stdin()
-            .read_line(&mut input)
-            .expect("failed to read user input");
+            // Saving isn't a move, so ask again without using up a try.
+            let mut words = input.split_whitespace();
+            match (words.next(), words.next()) {
+                (Some("save"), path) => {
+                    let path = Path::new(path.unwrap_or(SAVE_FILE));
+                    match self.save.as_mut().map(|save| save(&board, path)) {
+                        Some(Ok(())) => println!("Saved the game to {}.", path.display()),
+                        Some(Err(err)) => println!("Failed to save the game: {}", err),
+                        None => println!("This game can't be saved."),
+                    }
+                }
+                _ => break,
+            }
+        }
 
-        let pos = input.trim();
+        let pos = match input.trim().parse() {
+            Ok(pos) => pos,
+            Err(_) => {
+                let reason = format!("{:?} is not a position", input.trim());
+                return Err(ax::PlayerError::Invalid(board, reason));
+            }
+        };
 
         let mut board = board;
-        let res = board.place_piece(pos.parse().unwrap(), self.0);
-        if let Err(_) = res {
-            return self.take_turn(board);
+        match board.place_piece(pos) {
+            Ok(()) => Ok(board),
+            Err(err) => Err(ax::PlayerError::Invalid(board, err.to_string())),
         }
-        board
     }
 }
 
@@ -203,37 +486,27 @@ where
 /// spots on the board to place its piece.
 ///
 #[derive(Debug)]
-pub struct Random<P: fmt::Display + PartialEq + Default + Copy, R: Rng> {
-    piece: P,
-    rng: RefCell<R>,
+pub struct Random<R: Rng> {
+    rng: R,
 }
 
-impl<P, R> Random<P, R>
-where
-    P: fmt::Display + PartialEq + Default + Copy,
-    R: Rng,
-{
-    pub fn new(piece: P, rng: R) -> Self {
-        Random {
-            piece,
-            rng: RefCell::new(rng),
-        }
+impl<R: Rng> Random<R> {
+    pub fn new(rng: R) -> Self {
+        Random { rng }
     }
 }
 
-impl<P, R> ax::Player<Board<P>> for Random<P, R>
+impl<P, R> ax::Player<Board<P>> for Random<R>
 where
     P: fmt::Display + PartialEq + Default + Copy,
     R: Rng,
 {
-    fn take_turn(&mut self, board: Board<P>) -> Board<P> {
-        let pos = self.rng.borrow_mut().gen_range(0..9);
+    fn take_turn(&mut self, board: Board<P>) -> Result<Board<P>, ax::PlayerError<Board<P>>> {
+        let moves = board.legal_moves();
+        let pos = moves[self.rng.gen_range(0..moves.len())];
 
         let mut board = board;
-        let res = board.place_piece(pos, self.piece);
-        if let Err(_) = res {
-            return self.take_turn(board);
-        }
-        board
+        board.apply(pos);
+        Ok(board)
     }
 }
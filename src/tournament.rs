@@ -0,0 +1,435 @@
+//! Play AI against each other at tic-tac-toe to compare them.
+
+use std::error;
+use std::fmt;
+use std::io::{self, Write};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use crate as ax;
+use crate::combinator::{repeat_until_terminal, turns};
+use crate::players::{self, Options};
+use crate::render;
+use crate::seed::Streams;
+use crate::tictactoe::Board;
+use crate::train;
+use crate::{Action, State};
+
+/// z-score of a 95% confidence interval.
+const Z: f64 = 1.96;
+
+/// Wins, draws and losses of one AI.
+#[derive(Debug, Default, PartialEq, Copy, Clone)]
+pub struct Record {
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+}
+
+impl Record {
+    pub fn games(&self) -> usize {
+        self.wins + self.draws + self.losses
+    }
+
+    /// Returns the share of points scored, where a draw is worth half a win.
+    pub fn score(&self) -> f64 {
+        if self.games() == 0 {
+            return 0.0;
+        }
+        (self.wins as f64 + self.draws as f64 / 2.0) / self.games() as f64
+    }
+
+    /// Returns the variance of the points scored in a single game.
+    pub fn variance(&self) -> f64 {
+        let n = self.games() as f64;
+        if n == 0.0 {
+            return 0.0;
+        }
+        let p = self.score();
+        (self.wins as f64 * (1.0 - p).powi(2)
+            + self.draws as f64 * (0.5 - p).powi(2)
+            + self.losses as f64 * p.powi(2))
+            / n
+    }
+
+    /// Returns the margin of error of [`Record::score`] at 95% confidence.
+    pub fn margin(&self) -> f64 {
+        let n = self.games() as f64;
+        if n == 0.0 {
+            return 0.0;
+        }
+        Z * (self.variance() / n).sqrt()
+    }
+
+    /// Returns the same games from the opponent's point of view.
+    pub fn reversed(self) -> Self {
+        Self {
+            wins: self.losses,
+            draws: self.draws,
+            losses: self.wins,
+        }
+    }
+
+    pub fn add(&mut self, other: Record) {
+        self.wins += other.wins;
+        self.draws += other.draws;
+        self.losses += other.losses;
+    }
+}
+
+/// The games played between two AI, from the point of view of `ai`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Pairing {
+    pub ai: String,
+    pub opponent: String,
+    pub record: Record,
+    /// The result of each game, in the order they were started.
+    pub games: Vec<Record>,
+}
+
+/// Why a tournament couldn't be played.
+#[derive(Debug)]
+pub enum Error {
+    /// Only AI can enter a tournament.
+    Human,
+    /// An AI couldn't be created.
+    Player(train::Error),
+    /// A game couldn't be played to the end.
+    Game(String),
+}
+
+impl From<train::Error> for Error {
+    fn from(err: train::Error) -> Self {
+        Error::Player(err)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Human => write!(f, "humans can't play in a tournament"),
+            Error::Player(err) => write!(f, "{}", err),
+            Error::Game(reason) => write!(f, "game failed: {}", reason),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Player(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// Every AI plays `games` games against every other, taking turns to move first.
+#[derive(Debug, Clone)]
+pub struct Tournament {
+    pub ais: Vec<String>,
+    pub games: usize,
+    /// How many games are played at the same time.
+    pub threads: usize,
+    pub options: Options,
+}
+
+impl Tournament {
+    /// Creates every AI once, so a broken model fails before any game is played.
+    pub fn check(&self) -> Result<(), Error> {
+        for name in &self.ais {
+            if name == "human" {
+                return Err(Error::Human);
+            }
+            players::tic_tac_toe::<&str>(name, 0, &self.options)?;
+        }
+        Ok(())
+    }
+
+    /// Plays every game, or stops at the first one which fails.
+    pub fn run(&self) -> Result<Vec<Pairing>, Error> {
+        self.check()?;
+
+        let mut pairs = Vec::new();
+        for a in 0..self.ais.len() {
+            for b in a + 1..self.ais.len() {
+                pairs.push((a, b));
+            }
+        }
+        let jobs: Vec<(usize, usize)> = (0..pairs.len())
+            .flat_map(|pair| (0..self.games).map(move |game| (pair, game)))
+            .collect();
+
+        let next = AtomicUsize::new(0);
+        let results = Mutex::new(vec![vec![Record::default(); self.games]; pairs.len()]);
+        let failed = Mutex::new(None);
+        thread::scope(|scope| {
+            for _ in 0..self.threads.max(1) {
+                scope.spawn(|| {
+                    while let Some((pair, game)) = jobs.get(next.fetch_add(1, Ordering::Relaxed)) {
+                        match self.play(pairs[*pair], *game) {
+                            Ok(record) => results.lock().unwrap()[*pair][*game] = record,
+                            Err(err) => {
+                                // Leave no jobs for the other threads.
+                                next.store(jobs.len(), Ordering::Relaxed);
+                                failed.lock().unwrap().get_or_insert(err);
+                                break;
+                            }
+                        }
+                    }
+                });
+            }
+        });
+
+        if let Some(err) = failed.into_inner().unwrap() {
+            return Err(err);
+        }
+        let results = results.into_inner().unwrap();
+        Ok(pairs
+            .into_iter()
+            .zip(results)
+            .map(|((a, b), games)| {
+                let mut record = Record::default();
+                for game in &games {
+                    record.add(*game);
+                }
+                Pairing {
+                    ai: self.ais[a].clone(),
+                    opponent: self.ais[b].clone(),
+                    record,
+                    games,
+                }
+            })
+            .collect())
+    }
+
+    /// Plays a single game between a pair of AI, where the first of them
+    /// moves first in even games. Returns the result of the first AI.
+    pub fn play(&self, (a, b): (usize, usize), game: usize) -> Result<Record, Error> {
+        let seed = self.options.streams.seed();
+        let options = Options {
+            streams: Streams::new(seed.derive(&format!("tournament {} {} {}", a, b, game))),
+            ..self.options.clone()
+        };
+        let mut seats = [a, b];
+        if game % 2 == 1 {
+            seats.swap(0, 1);
+        }
+        let players = seats
+            .iter()
+            .enumerate()
+            .map(|(seat, ai)| players::tic_tac_toe(&self.ais[*ai], seat, &options))
+            .collect::<Result<_, _>>()?;
+
+        let mut run = repeat_until_terminal(turns(players));
+        let board = run
+            .apply(Board::new("X", "O"))
+            .map_err(|err| Error::Game(err.to_string()))?;
+        let outcome = match board.status() {
+            Ok(ax::Status::Terminal(outcome)) => outcome,
+            _ => return Err(Error::Game("the game ended early".to_string())),
+        };
+
+        let first = if seats[0] == a { 0 } else { 1 };
+        Ok(match outcome.winner() {
+            Some(seat) if seat == first => Record {
+                wins: 1,
+                ..Record::default()
+            },
+            Some(_) => Record {
+                losses: 1,
+                ..Record::default()
+            },
+            None => Record {
+                draws: 1,
+                ..Record::default()
+            },
+        })
+    }
+}
+
+/// Adds up the games of each AI, in the order they were entered.
+pub fn standings(pairings: &[Pairing]) -> Vec<(String, Record)> {
+    let mut standings: Vec<(String, Record)> = Vec::new();
+    let mut add = |ai: &str, record: Record| match standings.iter_mut().find(|(a, _)| a == ai) {
+        Some((_, total)) => total.add(record),
+        None => standings.push((ai.to_string(), record)),
+    };
+    for pairing in pairings {
+        add(&pairing.ai, pairing.record);
+        add(&pairing.opponent, pairing.record.reversed());
+    }
+    standings
+}
+
+/// How results are written.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Format {
+    Table,
+    Csv,
+    Json,
+}
+
+#[derive(Debug)]
+pub struct UnknownFormat(String);
+
+impl fmt::Display for UnknownFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown format {:?}", self.0)
+    }
+}
+
+impl error::Error for UnknownFormat {}
+
+impl FromStr for Format {
+    type Err = UnknownFormat;
+
+    fn from_str(s: &str) -> Result<Self, UnknownFormat> {
+        match s {
+            "table" => Ok(Format::Table),
+            "csv" => Ok(Format::Csv),
+            "json" => Ok(Format::Json),
+            _ => Err(UnknownFormat(s.to_string())),
+        }
+    }
+}
+
+/// Writes the results of a tournament.
+pub fn write(pairings: &[Pairing], format: Format, mut out: impl Write) -> io::Result<()> {
+    match format {
+        Format::Table => {
+            let width = pairings
+                .iter()
+                .flat_map(|p| vec![p.ai.len(), p.opponent.len()])
+                .max()
+                .unwrap_or(0)
+                .max("opponent".len());
+            writeln!(
+                out,
+                "{:w$}  {:w$}  {:>5}  {:>5}  {:>5}  {:>6}  {:>15}",
+                "ai",
+                "opponent",
+                "games",
+                "wins",
+                "draws",
+                "losses",
+                "score",
+                w = width
+            )?;
+            for Pairing {
+                ai,
+                opponent,
+                record,
+                ..
+            } in pairings
+            {
+                writeln!(
+                    out,
+                    "{:w$}  {:w$}  {}",
+                    ai,
+                    opponent,
+                    row(record),
+                    w = width
+                )?;
+            }
+            writeln!(out)?;
+            for (ai, record) in standings(pairings) {
+                writeln!(
+                    out,
+                    "{:w$}  {:w$}  {}",
+                    ai,
+                    "(total)",
+                    row(&record),
+                    w = width
+                )?;
+            }
+            Ok(())
+        }
+        Format::Csv => {
+            writeln!(out, "ai,opponent,games,wins,draws,losses,score,margin")?;
+            for Pairing {
+                ai,
+                opponent,
+                record,
+                ..
+            } in pairings
+            {
+                writeln!(
+                    out,
+                    "{},{},{},{},{},{},{:.4},{:.4}",
+                    csv(ai),
+                    csv(opponent),
+                    record.games(),
+                    record.wins,
+                    record.draws,
+                    record.losses,
+                    record.score(),
+                    record.margin()
+                )?;
+            }
+            Ok(())
+        }
+        Format::Json => {
+            let standings: Vec<String> = standings(pairings)
+                .iter()
+                .map(|(ai, record)| format!("{{\"ai\":{},{}}}", json(ai), json_record(record)))
+                .collect();
+            let pairings: Vec<String> = pairings
+                .iter()
+                .map(|p| {
+                    format!(
+                        "{{\"ai\":{},\"opponent\":{},{}}}",
+                        json(&p.ai),
+                        json(&p.opponent),
+                        json_record(&p.record)
+                    )
+                })
+                .collect();
+            writeln!(
+                out,
+                "{{\"pairings\":[{}],\"standings\":[{}]}}",
+                pairings.join(","),
+                standings.join(",")
+            )
+        }
+    }
+}
+
+fn row(record: &Record) -> String {
+    format!(
+        "{:>5}  {:>5}  {:>5}  {:>6}  {:>6.1}% ± {:>4.1}%",
+        record.games(),
+        record.wins,
+        record.draws,
+        record.losses,
+        record.score() * 100.0,
+        record.margin() * 100.0
+    )
+}
+
+fn csv(field: &str) -> String {
+    if field.contains(&[',', '"', '\n'][..]) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn json(s: &str) -> String {
+    let mut out = String::new();
+    render::json_string(&mut out, s).expect("writing into a string can't fail");
+    out
+}
+
+fn json_record(record: &Record) -> String {
+    format!(
+        "\"games\":{},\"wins\":{},\"draws\":{},\"losses\":{},\"score\":{:.4},\"margin\":{:.4}",
+        record.games(),
+        record.wins,
+        record.draws,
+        record.losses,
+        record.score(),
+        record.margin()
+    )
+}
@@ -0,0 +1,296 @@
+//! Train AI for each game.
+
+mod numberguesser;
+mod tictactoe;
+
+use std::error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use rand::Rng;
+
+use self::numberguesser::{NetworkGuesser, RecurrentGuesser};
+use self::tictactoe::network_player;
+use crate::ai::file::{self, ModelFile, Trained};
+use crate::ai::nn::{Activation, Loss, Network, Optimizer};
+use crate::numberguesser::State;
+use crate::seed::{Seed, Stream};
+use crate::tictactoe::Board;
+use crate::Player;
+
+/// Settings shared by every kind of training.
+#[derive(Debug, Clone)]
+pub struct Settings {
+    /// How many epochs to train for.
+    pub epochs: usize,
+    /// How many games are played each epoch.
+    pub games: usize,
+    /// Number of genomes in each generation when evolving with neat.
+    pub population: usize,
+    /// Number of nodes in each hidden layer. Each trainer picks its own if empty.
+    pub layers: Vec<usize>,
+    /// Activation of the hidden layers.
+    pub activation: Activation,
+    pub loss: Loss,
+    pub optimizer: Optimizer,
+    /// Recorded games to learn from instead of self-play.
+    pub recorded: Option<PathBuf>,
+    /// Training draws from the `train` stream of this seed.
+    pub seed: Seed,
+}
+
+impl Settings {
+    /// Records these settings in a model file.
+    fn describe(&self, file: ModelFile) -> ModelFile {
+        let mut file = file
+            .meta("epochs", self.epochs)
+            .meta("games", self.games)
+            .meta("population", self.population);
+        if !self.layers.is_empty() {
+            let layers: Vec<String> = self.layers.iter().map(|l| l.to_string()).collect();
+            file = file.meta("layers", layers.join(" "));
+        }
+        file.meta("seed", self.seed)
+            .meta("activation", self.activation)
+            .meta("loss", self.loss)
+            .meta("optimizer", self.optimizer)
+    }
+
+    /// Creates a network with `sizes` set up as asked. Its outputs lie between
+    /// `-1` and `1`, or `0` and `1` when it is trained with cross-entropy.
+    fn network(&self, sizes: &[usize], rng: &mut impl Rng) -> Network {
+        let output = match self.loss {
+            Loss::MeanSquared => Activation::Tanh,
+            Loss::CrossEntropy => Activation::Sigmoid,
+        };
+        Network::new(sizes, rng)
+            .activations(self.activation, output)
+            .loss(self.loss)
+            .optimizer(self.optimizer)
+    }
+}
+
+/// Rescales `target`, between `-1` and `1`, to fit the outputs of a network
+/// made by [`Settings::network`] and trained with `loss`.
+fn target_for(loss: Loss, target: f64) -> f64 {
+    match loss {
+        Loss::MeanSquared => target,
+        Loss::CrossEntropy => (target + 1.0) / 2.0,
+    }
+}
+
+/// A model being trained for a game.
+pub trait Trainer {
+    type Report: fmt::Display;
+
+    /// Runs a single epoch of training and reports how it went.
+    fn epoch(&mut self) -> Self::Report;
+
+    /// Returns the model trained so far.
+    fn model(&self) -> Trained;
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Unsupported(String),
+    /// The settings can't be trained with.
+    Settings(String),
+    Io(io::Error),
+    /// A model file couldn't be loaded.
+    Model(PathBuf, file::Error),
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Unsupported(msg) => write!(f, "{}", msg),
+            Error::Settings(msg) => write!(f, "{}", msg),
+            Error::Io(err) => write!(f, "{}", err),
+            Error::Model(path, err) => write!(f, "{}: {}", path.display(), err),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+/// Train a `model` with `alg` to play `game` and save it to `path`.
+pub fn train(
+    model: &str,
+    alg: &str,
+    game: &str,
+    settings: &Settings,
+    path: &Path,
+) -> Result<(), Error> {
+    let rng = settings.seed.stream("train");
+    if settings.layers.contains(&0) {
+        return Err(Error::Settings(
+            "hidden layers need at least one node".to_string(),
+        ));
+    }
+    if settings.recorded.is_some() && (alg, game) != ("backprop", "tic-tac-toe") {
+        return Err(Error::Unsupported(format!(
+            "training from recorded games is only supported with backprop for tic-tac-toe, not {} for {}",
+            alg, game
+        )));
+    }
+
+    let file = |trained| {
+        settings.describe(
+            ModelFile::new(game, trained)
+                .meta("model", model)
+                .meta("algorithm", alg),
+        )
+    };
+
+    match (model, alg, game) {
+        ("nueral-network", "backprop", "tic-tac-toe") => {
+            let mut trainer = tictactoe::Backprop::new(settings, rng);
+            if let Some(recorded) = &settings.recorded {
+                trainer = trainer.recorded(read(recorded, tictactoe::read_games)?);
+            }
+            run(trainer, file, settings, path)
+        }
+        ("nueral-network", "neat", "tic-tac-toe") => {
+            run(tictactoe::Neat::new(settings, rng)?, file, settings, path)
+        }
+        ("nueral-network", "backprop", "number-guesser") => run(
+            numberguesser::Backprop::new(settings, rng),
+            file,
+            settings,
+            path,
+        ),
+        ("recurrent-nueral-network", "backprop", "number-guesser") => {
+            if settings.layers.len() > 1 {
+                return Err(Error::Unsupported(
+                    "a recurrent network has a single hidden layer".to_string(),
+                ));
+            }
+            run(
+                numberguesser::Bptt::new(settings, rng),
+                file,
+                settings,
+                path,
+            )
+        }
+        _ => Err(Error::Unsupported(format!(
+            "training a {} with {} for {} is not supported yet",
+            model, alg, game
+        ))),
+    }
+}
+
+/// Train for every epoch, reporting progress along the way, then save the
+/// model to `path` in the file made by `file`.
+fn run(
+    mut trainer: impl Trainer,
+    file: impl FnOnce(Trained) -> ModelFile,
+    settings: &Settings,
+    path: &Path,
+) -> Result<(), Error> {
+    let mut result = None;
+    for epoch in 1..=settings.epochs {
+        let report = trainer.epoch();
+        println!("epoch {}/{}: {}", epoch, settings.epochs, report);
+        result = Some(report.to_string());
+    }
+
+    let mut file = file(trainer.model());
+    if let Some(result) = result {
+        file = file.meta("result", result);
+    }
+    let mut out = BufWriter::new(File::create(path)?);
+    file.write(&mut out)?;
+    out.flush()?;
+
+    println!("saved model to {}", path.display());
+    Ok(())
+}
+
+/// Load a model trained by `ax train`, checking it was trained for `game`.
+pub fn load(path: &Path, game: &str) -> Result<ModelFile, Error> {
+    let in_file = |err| Error::Model(path.to_path_buf(), err);
+    let file = File::open(path).map_err(|err| in_file(err.into()))?;
+    ModelFile::read(BufReader::new(file))
+        .and_then(|file| file.for_game(game))
+        .map_err(in_file)
+}
+
+/// Load a tic-tac-toe player from a model trained by `ax train`.
+pub fn load_tic_tac_toe<P>(path: &Path, rng: Stream) -> Result<Box<dyn Player<Board<P>>>, Error>
+where
+    P: PartialEq + Default + Copy + 'static,
+{
+    let model = load(path, "tic-tac-toe")?.model;
+    check_shape(path, &model, crate::tictactoe::FEATURES)?;
+    match model {
+        Trained::Network(net) => Ok(Box::new(network_player(net, rng))),
+        Trained::Genome(genome) => Ok(Box::new(network_player(genome.network(), rng))),
+        model => Err(Error::Unsupported(format!(
+            "{}: a {} model can't play tic-tac-toe",
+            path.display(),
+            model.kind()
+        ))),
+    }
+}
+
+/// Load a number-guesser player from a model trained by `ax train`.
+pub fn load_guesser(path: &Path, rng: Stream) -> Result<Box<dyn Player<State>>, Error> {
+    let model = load(path, "number-guesser")?.model;
+    let inputs = match model {
+        Trained::Recurrent(_) => numberguesser::INPUTS,
+        _ => numberguesser::FEATURES,
+    };
+    check_shape(path, &model, inputs)?;
+    match model {
+        Trained::Network(net) => Ok(Box::new(NetworkGuesser::new(net, rng))),
+        Trained::Recurrent(net) => Ok(Box::new(RecurrentGuesser::new(net, rng))),
+        model => Err(Error::Unsupported(format!(
+            "{}: a {} model can't play number-guesser",
+            path.display(),
+            model.kind()
+        ))),
+    }
+}
+
+/// Checks `model` takes as many inputs as a player feeds it and has an
+/// output to be scored by.
+fn check_shape(path: &Path, model: &Trained, inputs: usize) -> Result<(), Error> {
+    let (found, outputs) = match model {
+        Trained::Network(net) => {
+            let sizes = net.sizes();
+            (sizes[0], sizes[sizes.len() - 1])
+        }
+        Trained::Recurrent(net) => {
+            let (inputs, _, outputs) = net.sizes();
+            (inputs, outputs)
+        }
+        Trained::Genome(genome) => genome.sizes(),
+    };
+    let msg = if found != inputs {
+        format!(
+            "the model takes {} inputs, but the game gives it {}",
+            found, inputs
+        )
+    } else if outputs == 0 {
+        "the model has no outputs".to_string()
+    } else {
+        return Ok(());
+    };
+    Err(Error::Model(path.to_path_buf(), file::Error::Format(msg)))
+}
+
+/// Read a file other than a model, such as recorded games.
+fn read<T>(path: &Path, read: fn(BufReader<File>) -> io::Result<T>) -> Result<T, Error> {
+    let in_file =
+        |err: io::Error| io::Error::new(err.kind(), format!("{}: {}", path.display(), err));
+    let file = File::open(path).map_err(in_file)?;
+    read(BufReader::new(file)).map_err(|err| in_file(err).into())
+}
@@ -0,0 +1,316 @@
+use std::borrow::Borrow;
+use std::fmt;
+
+use rand::Rng;
+
+use super::{target_for, Settings, Trainer};
+use crate as ax;
+use crate::ai::file::Trained;
+use crate::ai::nn::{Loss, Network};
+use crate::ai::rnn::Recurrent;
+use crate::ai::Model;
+use crate::combinator::{map_action, repeat_until_terminal, take_turn};
+use crate::numberguesser::{Hint, State};
+use crate::{Action, Game, Player, PlayerError};
+
+/// How often a random guess is made while training.
+const EXPLORATION: f64 = 0.1;
+
+/// Range of numbers games are played with while training.
+const LOW: i64 = 0;
+const HIGH: i64 = 100;
+
+/// Guesses needed after a guess are divided by this to fit the network's output.
+const SCALE: f64 = 10.0;
+
+/// How many values [`features`] describes a guess with.
+pub const FEATURES: usize = 3;
+
+/// How many values each input of [`sequence`] holds.
+pub const INPUTS: usize = 3;
+
+/// Describes guessing `guess` in `state`, for use as the input of a nueral network.
+fn features(state: &State, guess: i64) -> Vec<f64> {
+    let (low, high) = state.bounds();
+    let (min, max) = state.range();
+
+    let width = (high - low) as f64;
+    let x = if width > 0.0 {
+        (guess - low) as f64 / width
+    } else {
+        0.5
+    };
+    vec![x, x * x, (width + 1.0) / (max - min) as f64]
+}
+
+/// Guesses the number its network thinks will need the fewest guesses after it.
+///
+/// Only the guesses listed by [`Game::legal_moves`] are scored, which are
+/// at most [`MAX_MOVES`](crate::numberguesser::MAX_MOVES) however wide the
+/// range is.
+#[derive(Debug)]
+pub struct NetworkGuesser<M, R> {
+    net: M,
+    epsilon: f64,
+    rng: R,
+}
+
+impl<M: Model, R: Rng> NetworkGuesser<M, R> {
+    pub fn new(net: M, rng: R) -> Self {
+        Self {
+            net,
+            epsilon: 0.0,
+            rng,
+        }
+    }
+
+    /// Guess randomly instead with a probability of `epsilon`.
+    pub fn explore(mut self, epsilon: f64) -> Self {
+        self.epsilon = epsilon;
+        self
+    }
+}
+
+impl<M: Model, R: Rng> Player<State> for NetworkGuesser<M, R> {
+    fn take_turn(&mut self, state: State) -> Result<State, PlayerError<State>> {
+        let (low, high) = state.bounds();
+
+        let guess = if self.rng.gen_bool(self.epsilon) {
+            self.rng.gen_range(low..=high)
+        } else {
+            state
+                .legal_moves()
+                .into_iter()
+                .map(|g| (g, self.net.forward(&features(&state, g))[0]))
+                .max_by(|(_, a), (_, b)| a.total_cmp(b))
+                .map(|(g, _)| g)
+                .unwrap()
+        };
+
+        let mut state = state;
+        match state.guess(guess) {
+            Ok(()) => Ok(state),
+            Err(err) => Err(PlayerError::Invalid(state, err.to_string())),
+        }
+    }
+}
+
+/// Learns to score guesses by how many more guesses were needed after them.
+pub struct Backprop<R> {
+    net: Network,
+    games: usize,
+    loss: Loss,
+    rng: R,
+}
+
+impl<R: Rng> Backprop<R> {
+    pub fn new(settings: &Settings, mut rng: R) -> Self {
+        let mut sizes = vec![FEATURES];
+        if settings.layers.is_empty() {
+            sizes.push(8);
+        }
+        sizes.extend(&settings.layers);
+        sizes.push(1);
+
+        Self {
+            net: settings.network(&sizes, &mut rng),
+            games: settings.games,
+            loss: settings.loss,
+            rng,
+        }
+    }
+
+    /// Plays a game and returns the state after every guess.
+    fn play(&mut self, epsilon: f64) -> Vec<State> {
+        let mut history = Vec::new();
+
+        let state = State::new(LOW, HIGH, &mut self.rng);
+        let player = NetworkGuesser::new(&self.net, &mut self.rng).explore(epsilon);
+        let mut run = repeat_until_terminal(map_action(take_turn(player), |state: State| {
+            history.push(state.clone());
+            Ok(state)
+        }));
+        run.apply(state).expect("network players can't fail");
+        drop(run);
+
+        history
+    }
+}
+
+pub struct Report {
+    loss: f64,
+    guesses: f64,
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "loss {:.4}, average guesses {:.2}",
+            self.loss, self.guesses
+        )
+    }
+}
+
+impl<R: Rng> Trainer for Backprop<R> {
+    type Report = Report;
+
+    fn epoch(&mut self) -> Report {
+        let mut loss = 0.0;
+        let mut samples = 0;
+        for _ in 0..self.games {
+            let history = self.play(EXPLORATION);
+
+            for (i, after) in history.iter().enumerate() {
+                let guess = after.last_guess().unwrap();
+                let mut before = after.clone();
+                ax::Game::undo(&mut before, guess);
+
+                let remaining = (history.len() - i - 1) as f64;
+                let target = (-remaining / SCALE).max(-1.0);
+                loss += self
+                    .net
+                    .train(&features(&before, guess), &[target_for(self.loss, target)]);
+                samples += 1;
+            }
+        }
+
+        let guesses: usize = (0..self.games).map(|_| self.play(0.0).len()).sum();
+        Report {
+            loss: loss / samples.max(1) as f64,
+            guesses: guesses as f64 / self.games.max(1) as f64,
+        }
+    }
+
+    fn model(&self) -> Trained {
+        Trained::Network(self.net.clone())
+    }
+}
+
+/// Describes the feedback on every guess in `state`, for use as the inputs
+/// of a recurrent network. The first input comes before any guess, so
+/// there is an input for every guess the network has to make.
+fn sequence(state: &State) -> Vec<Vec<f64>> {
+    let (min, max) = state.range();
+    let mut inputs = vec![vec![0.0; INPUTS]];
+    inputs.extend(state.history().into_iter().map(|(guess, hint)| {
+        let x = (guess - min) as f64 / (max - 1 - min).max(1) as f64;
+        vec![
+            x,
+            (hint == Hint::TooLow) as u8 as f64,
+            (hint == Hint::TooHigh) as u8 as f64,
+        ]
+    }));
+    inputs
+}
+
+/// Guesses where its recurrent network, having seen the feedback on every
+/// guess so far, expects the number to be.
+///
+/// The network predicts the number scaled to fall between `-1` and `1`.
+/// Guesses are kept within [`State::bounds`] so none are wasted.
+#[derive(Debug)]
+pub struct RecurrentGuesser<N, R> {
+    net: N,
+    epsilon: f64,
+    rng: R,
+}
+
+impl<N: Borrow<Recurrent>, R: Rng> RecurrentGuesser<N, R> {
+    pub fn new(net: N, rng: R) -> Self {
+        Self {
+            net,
+            epsilon: 0.0,
+            rng,
+        }
+    }
+
+    /// Guess randomly instead with a probability of `epsilon`.
+    pub fn explore(mut self, epsilon: f64) -> Self {
+        self.epsilon = epsilon;
+        self
+    }
+}
+
+impl<N: Borrow<Recurrent>, R: Rng> Player<State> for RecurrentGuesser<N, R> {
+    fn take_turn(&mut self, state: State) -> Result<State, PlayerError<State>> {
+        let (low, high) = state.bounds();
+
+        let guess = if self.rng.gen_bool(self.epsilon) {
+            self.rng.gen_range(low..=high)
+        } else {
+            let (min, max) = state.range();
+            let outputs = self.net.borrow().run(&sequence(&state));
+            let x = (outputs.last().unwrap()[0] + 1.0) / 2.0;
+            let guess = min + (x * (max - 1 - min) as f64).round() as i64;
+            guess.clamp(low, high)
+        };
+
+        let mut state = state;
+        match state.guess(guess) {
+            Ok(()) => Ok(state),
+            Err(err) => Err(PlayerError::Invalid(state, err.to_string())),
+        }
+    }
+}
+
+/// Teaches a recurrent network to predict the number from the feedback on
+/// the guesses so far, with backpropagation through time. The best
+/// prediction is the middle of the numbers left, so it learns to search.
+pub struct Bptt<R> {
+    net: Recurrent,
+    games: usize,
+    rng: R,
+}
+
+impl<R: Rng> Bptt<R> {
+    pub fn new(settings: &Settings, mut rng: R) -> Self {
+        let hidden = settings.layers.first().copied().unwrap_or(16);
+        Self {
+            net: Recurrent::new(INPUTS, hidden, 1, &mut rng).optimizer(settings.optimizer),
+            games: settings.games,
+            rng,
+        }
+    }
+
+    /// Plays a game and returns the final state.
+    fn play(&mut self, epsilon: f64) -> State {
+        let state = State::new(LOW, HIGH, &mut self.rng);
+        let player = RecurrentGuesser::new(&self.net, &mut self.rng).explore(epsilon);
+        let mut run = repeat_until_terminal(take_turn(player));
+        run.apply(state).expect("network players can't fail")
+    }
+}
+
+impl<R: Rng> Trainer for Bptt<R> {
+    type Report = Report;
+
+    fn epoch(&mut self) -> Report {
+        let mut loss = 0.0;
+        for _ in 0..self.games {
+            let state = self.play(EXPLORATION);
+
+            // Every input is followed by a guess, except for the last one
+            // which is the feedback on the correct guess.
+            let mut inputs = sequence(&state);
+            inputs.pop();
+            let number = state.last_guess().unwrap();
+            let target = (number - LOW) as f64 / (HIGH - 1 - LOW) as f64 * 2.0 - 1.0;
+            let targets = vec![vec![target]; inputs.len()];
+
+            loss += self.net.train(&inputs, &targets);
+        }
+
+        let guesses: usize = (0..self.games)
+            .map(|_| self.play(0.0).history().len())
+            .sum();
+        Report {
+            loss: loss / self.games.max(1) as f64,
+            guesses: guesses as f64 / self.games.max(1) as f64,
+        }
+    }
+
+    fn model(&self) -> Trained {
+        Trained::Recurrent(self.net.clone())
+    }
+}
@@ -0,0 +1,312 @@
+use std::fmt;
+use std::io::{self, BufRead};
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use super::{target_for, Error, Settings, Trainer};
+use crate::ai::file::Trained;
+use crate::ai::neat::{self, Population};
+use crate::ai::nn::{Loss, Network};
+use crate::ai::{Model, ModelPlayer};
+use crate::combinator::{map_action, repeat_until_terminal, take_turn, turns};
+use crate::tictactoe::{Board, Random, FEATURES};
+use crate::transcript::Transcript;
+use crate::{Action, Game, Player, State};
+
+/// How often a random move is played during self-play.
+const EXPLORATION: f64 = 0.1;
+
+/// How many games are played against [`Random`] after each epoch.
+const EVALUATION_GAMES: usize = 100;
+
+/// Plays tic-tac-toe by picking the move its network scores highest.
+pub type NetworkPlayer<P, M, R> = ModelPlayer<(), Board<P>, M, R>;
+
+/// Creates a [`NetworkPlayer`] which scores boards by their [`Board::features`].
+pub fn network_player<P, M, R>(net: M, rng: R) -> NetworkPlayer<P, M, R>
+where
+    P: PartialEq + Default + Copy,
+    M: Model,
+    R: Rng,
+{
+    ModelPlayer::new(net, Board::features, rng)
+}
+
+/// Plays `games` games against [`Random`], alternating who goes first,
+/// and returns how many were won, drawn and lost.
+fn versus_random(
+    mut player: impl Player<Board<char>>,
+    games: usize,
+    rng: &mut impl Rng,
+) -> (usize, usize, usize) {
+    let (mut won, mut drawn, mut lost) = (0, 0, 0);
+    for game in 0..games {
+        let seat = game % 2;
+
+        let mut players: Vec<Box<dyn Player<Board<char>> + '_>> =
+            vec![Box::new(&mut player), Box::new(Random::new(&mut *rng))];
+        players.swap(0, seat);
+
+        let mut run = repeat_until_terminal(turns(players));
+        let board = run
+            .apply(Board::new('X', 'O'))
+            .expect("ai players can't fail");
+
+        match board
+            .status()
+            .ok()
+            .and_then(|s| s.outcome().and_then(|o| o.winner()))
+        {
+            Some(winner) if winner == seat => won += 1,
+            Some(_) => lost += 1,
+            None => drawn += 1,
+        }
+    }
+    (won, drawn, lost)
+}
+
+/// Reads recorded games from a [transcript](crate::transcript), as written
+/// by `play --record`, returning the positions played in each of them from
+/// an empty board.
+///
+/// A game which was cut short is learnt from as a draw.
+pub fn read_games(mut input: impl BufRead) -> io::Result<Vec<Vec<usize>>> {
+    let mut games = Vec::new();
+    while !input.fill_buf()?.is_empty() {
+        let game = Transcript::read(&mut input)
+            .and_then(|transcript| transcript.replay::<(), _>(Board::new('X', 'O')))
+            .map_err(|err| {
+                io::Error::new(err.kind(), format!("game {}: {}", games.len() + 1, err))
+            })?;
+        games.push(game.moves());
+    }
+    Ok(games)
+}
+
+/// Learns to score positions by playing against itself, or from recorded games.
+///
+/// After every game each position is trained towards `1` if the player
+/// who moved into it won, `-1` if they lost and `0` for a draw.
+pub struct Backprop<R> {
+    net: Network,
+    games: usize,
+    loss: Loss,
+    recorded: Vec<Vec<usize>>,
+    rng: R,
+}
+
+impl<R: Rng> Backprop<R> {
+    pub fn new(settings: &Settings, mut rng: R) -> Self {
+        let mut sizes = vec![FEATURES];
+        if settings.layers.is_empty() {
+            sizes.push(FEATURES);
+        }
+        sizes.extend(&settings.layers);
+        sizes.push(1);
+
+        Self {
+            net: settings.network(&sizes, &mut rng),
+            games: settings.games,
+            loss: settings.loss,
+            recorded: Vec::new(),
+            rng,
+        }
+    }
+
+    /// Learn from these games, read by [`read_games`], instead of self-play.
+    pub fn recorded(mut self, games: Vec<Vec<usize>>) -> Self {
+        self.recorded = games;
+        self
+    }
+
+    /// Replays a recorded game and returns every position it went through.
+    fn replay(moves: &[usize]) -> Vec<Board<char>> {
+        let mut history = vec![Board::new('X', 'O')];
+        for mv in moves {
+            let mut board = history.last().unwrap().clone();
+            board.apply(*mv);
+            history.push(board);
+        }
+        history
+    }
+
+    /// Plays a game against itself and returns every position it went through.
+    fn self_play(&mut self) -> Vec<Board<char>> {
+        let mut history = vec![Board::new('X', 'O')];
+
+        let player = network_player(&self.net, &mut self.rng).explore(EXPLORATION);
+        let mut run = repeat_until_terminal(map_action(take_turn(player), |board: Board<char>| {
+            history.push(board.clone());
+            Ok(board)
+        }));
+        run.apply(Board::new('X', 'O'))
+            .expect("network players can't fail");
+        drop(run);
+
+        history
+    }
+
+    /// Plays against [`Random`] and returns how many games were won, drawn and lost.
+    fn evaluate(&mut self) -> (usize, usize, usize) {
+        let mut rng = StdRng::from_rng(&mut self.rng).unwrap();
+        versus_random(
+            network_player(&self.net, &mut self.rng),
+            EVALUATION_GAMES,
+            &mut rng,
+        )
+    }
+}
+
+pub struct Report {
+    loss: f64,
+    won: usize,
+    drawn: usize,
+    lost: usize,
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "loss {:.4}, vs random: {} won, {} drawn, {} lost",
+            self.loss, self.won, self.drawn, self.lost
+        )
+    }
+}
+
+impl<R: Rng> Trainer for Backprop<R> {
+    type Report = Report;
+
+    fn epoch(&mut self) -> Report {
+        let mut loss = 0.0;
+        let mut samples = 0;
+        let games = if self.recorded.is_empty() {
+            self.games
+        } else {
+            self.recorded.len()
+        };
+        for game in 0..games {
+            let history = match self.recorded.get(game) {
+                Some(moves) => Self::replay(moves),
+                None => self.self_play(),
+            };
+
+            let last = history.last().unwrap();
+            let winner = last
+                .status()
+                .ok()
+                .and_then(|s| s.outcome().and_then(|o| o.winner()));
+
+            for pair in history.windows(2) {
+                let mover = pair[0].current_player();
+                let target = match winner {
+                    Some(w) if w == mover => 1.0,
+                    Some(_) => -1.0,
+                    None => 0.0,
+                };
+                loss += self
+                    .net
+                    .train(&pair[1].features(mover), &[target_for(self.loss, target)]);
+                samples += 1;
+            }
+        }
+
+        let (won, drawn, lost) = self.evaluate();
+        Report {
+            loss: loss / samples.max(1) as f64,
+            won,
+            drawn,
+            lost,
+        }
+    }
+
+    fn model(&self) -> Trained {
+        Trained::Network(self.net.clone())
+    }
+}
+
+/// Evolves networks with NEAT, where a genome's fitness is how well
+/// it plays against [`Random`]: a point for a win, half for a draw.
+pub struct Neat<R> {
+    population: Population,
+    games: usize,
+    rng: R,
+}
+
+impl<R: Rng> Neat<R> {
+    pub fn new(settings: &Settings, mut rng: R) -> Result<Self, Error> {
+        if settings.population == 0 {
+            return Err(Error::Settings(
+                "a population needs at least one genome".to_string(),
+            ));
+        }
+        let config = neat::Config {
+            population: settings.population,
+            ..Default::default()
+        };
+
+        Ok(Self {
+            population: Population::new(FEATURES, 1, config, &mut rng),
+            games: settings.games,
+            rng,
+        })
+    }
+}
+
+pub struct Generation {
+    stats: neat::Generation,
+    champion: String,
+    won: usize,
+    drawn: usize,
+    lost: usize,
+}
+
+impl fmt::Display for Generation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "best fitness {:.1}, mean {:.1}, {} species, champion has {}, vs random: {} won, {} drawn, {} lost",
+            self.stats.best,
+            self.stats.mean,
+            self.stats.species,
+            self.champion,
+            self.won,
+            self.drawn,
+            self.lost
+        )
+    }
+}
+
+impl<R: Rng> Trainer for Neat<R> {
+    type Report = Generation;
+
+    fn epoch(&mut self) -> Generation {
+        let games = self.games;
+        let mut rng = StdRng::from_rng(&mut self.rng).unwrap();
+        let stats = self.population.evolve(
+            |genome| {
+                let player = network_player(genome.network(), StdRng::from_rng(&mut rng).unwrap());
+                let (won, drawn, _) = versus_random(player, games, &mut rng);
+                won as f64 + drawn as f64 / 2.0
+            },
+            &mut self.rng,
+        );
+
+        let champion = self.population.champion().expect("a generation was scored");
+        let player = network_player(champion.network(), &mut rng);
+        let (won, drawn, lost) = versus_random(player, EVALUATION_GAMES, &mut self.rng);
+        Generation {
+            stats,
+            champion: champion.to_string(),
+            won,
+            drawn,
+            lost,
+        }
+    }
+
+    fn model(&self) -> Trained {
+        let champion = self.population.champion();
+        Trained::Genome(champion.unwrap_or(&self.population.genomes()[0]).clone())
+    }
+}
@@ -0,0 +1,280 @@
+//! Transcripts of games, as written by [`record`](crate::combinator::record)
+//! and read by [`replay`](crate::combinator::replay).
+//!
+//! A transcript is plain text. It starts with a header line, the name of the
+//! game and the position it started from, followed by a line for each move
+//! and a line for the outcome once the game is over:
+//!
+//! ```text
+//! ax-transcript 2
+//! game number-guesser
+//! range 0 100
+//! number 42
+//! guesses
+//! move 0 1700000000000 1250 50
+//! move 0 1700000000002 83 25
+//! ...
+//! end winner 0
+//! ```
+//!
+//! The starting position is written in the same `key values...` lines as a
+//! [snapshot](crate::snapshot), so the game can be set up again to replay
+//! it. Each move line holds the seat of the player who moved, when their turn
+//! started in milliseconds since the Unix epoch, how long they took in
+//! microseconds and the move itself, which runs to the end of the line. The
+//! outcome is `winner <seat>`, `draw` or `scores` followed by the score of
+//! each seat. A game which was cut short has no end line, and several games
+//! can follow each other in the same file.
+
+use std::fmt;
+use std::io::{self, BufRead, Write};
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use tracing::trace;
+
+use crate::snapshot::{Fields, Position};
+use crate::{Game, Outcome};
+
+/// The version of the format written by [`Transcript::write`].
+pub const VERSION: u32 = 2;
+
+/// A single move of a game.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Turn<M> {
+    /// The seat of the player who made the move.
+    pub seat: usize,
+    /// When the turn started.
+    pub time: SystemTime,
+    /// How long the player took to move.
+    pub spent: Duration,
+    pub mv: M,
+}
+
+impl<M: fmt::Display> fmt::Display for Turn<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let time = self.time.duration_since(UNIX_EPOCH).unwrap_or_default();
+        write!(
+            f,
+            "move {} {} {} {}",
+            self.seat,
+            time.as_millis(),
+            self.spent.as_micros(),
+            self.mv
+        )
+    }
+}
+
+/// Everything that happened in a game.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Transcript<M> {
+    /// The name of the game, see [`Position::GAME`].
+    pub game: String,
+    /// The position the game started from, see [`Position::fields`].
+    pub setup: Fields,
+    pub turns: Vec<Turn<M>>,
+    /// How the game ended, or `None` if it was cut short.
+    pub outcome: Option<Outcome>,
+}
+
+impl<M> Transcript<M> {
+    /// Creates an empty transcript of a game starting from `start`.
+    pub fn new<S: Position>(start: &S) -> Self {
+        Self {
+            game: S::GAME.to_string(),
+            setup: Fields::of(start),
+            turns: Vec::new(),
+            outcome: None,
+        }
+    }
+
+    /// Sets up the position the game started from, where `new` is a new
+    /// game giving anything the transcript leaves out, like the pieces used.
+    pub fn start<S: Position>(&self, new: S) -> io::Result<S> {
+        if self.game != S::GAME {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("this is a transcript of {}, not {}", self.game, S::GAME),
+            ));
+        }
+        new.restore(&self.setup).map_err(|msg| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid starting position: {}", msg),
+            )
+        })
+    }
+
+    /// Plays the moves from the position the game started from, checking
+    /// each move is made by the player whose turn it is and is legal, and
+    /// that the game ends the same way. `new` is as for [`Transcript::start`].
+    pub fn replay<E, S>(self, new: S) -> io::Result<S>
+    where
+        S: Game<E, Move = M> + Position,
+        M: fmt::Display,
+    {
+        let mut state = self.start(new)?;
+        let wrong = |n: usize, msg: String| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("move {}: {}", n, msg))
+        };
+        for (i, turn) in self.turns.into_iter().enumerate() {
+            if turn.seat != state.current_player() {
+                return Err(wrong(
+                    i + 1,
+                    format!(
+                        "seat {} moved on the turn of seat {}",
+                        turn.seat,
+                        state.current_player()
+                    ),
+                ));
+            }
+            if !state.is_legal(&turn.mv) {
+                return Err(wrong(i + 1, format!("{} is not a legal move", turn.mv)));
+            }
+            trace!(seat = turn.seat, mv = %turn.mv, "replayed a move");
+            Game::apply(&mut state, turn.mv);
+        }
+
+        if let Some(expected) = self.outcome {
+            let outcome = state.status().ok().and_then(|s| s.outcome().cloned());
+            if outcome.as_ref() != Some(&expected) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "transcript ends with {:?} but the game ended with {:?}",
+                        expected, outcome
+                    ),
+                ));
+            }
+        }
+        Ok(state)
+    }
+}
+
+impl<M: fmt::Display> Transcript<M> {
+    pub fn write(&self, mut out: impl Write) -> io::Result<()> {
+        write_header(&mut out, &self.game, &self.setup)?;
+        for turn in &self.turns {
+            writeln!(out, "{}", turn)?;
+        }
+        match &self.outcome {
+            Some(outcome) => write_end(out, outcome),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<M: FromStr> Transcript<M> {
+    /// Reads the next game of a transcript, stopping after its end line so
+    /// any game following it can be read by another call.
+    pub fn read(mut input: impl BufRead) -> io::Result<Self> {
+        let mut line = String::new();
+        input.read_line(&mut line)?;
+        match line.trim_end().strip_prefix("ax-transcript ") {
+            Some(v) if v == VERSION.to_string() => {}
+            Some(v) => {
+                return Err(invalid(
+                    1,
+                    &format!("transcript version {} is not supported", v),
+                ))
+            }
+            None => return Err(invalid(1, "missing ax-transcript header")),
+        }
+
+        line.clear();
+        input.read_line(&mut line)?;
+        let game = match line.trim_end().strip_prefix("game ") {
+            Some(game) => game.to_string(),
+            None => return Err(invalid(2, "missing game")),
+        };
+
+        let mut transcript = Self {
+            game,
+            setup: Fields::default(),
+            turns: Vec::new(),
+            outcome: None,
+        };
+        let mut at = 2;
+        loop {
+            line.clear();
+            if input.read_line(&mut line)? == 0 {
+                break;
+            }
+            at += 1;
+            let text = line.trim_end_matches(&['\r', '\n'][..]);
+            let fields: Vec<&str> = text.splitn(5, ' ').collect();
+            match fields[0] {
+                "move" if fields.len() == 5 => {
+                    let number = |i: usize| -> io::Result<u64> {
+                        fields[i]
+                            .parse()
+                            .map_err(|_| invalid(at, &format!("invalid number {:?}", fields[i])))
+                    };
+                    transcript.turns.push(Turn {
+                        seat: number(1)? as usize,
+                        time: UNIX_EPOCH + Duration::from_millis(number(2)?),
+                        spent: Duration::from_micros(number(3)?),
+                        mv: fields[4]
+                            .parse()
+                            .map_err(|_| invalid(at, &format!("invalid move {:?}", fields[4])))?,
+                    });
+                }
+                "end" => {
+                    let words: Vec<&str> = text.split(' ').skip(1).collect();
+                    let outcome = read_outcome(&words)
+                        .ok_or_else(|| invalid(at, &format!("invalid outcome {:?}", text)))?;
+                    transcript.outcome = Some(outcome);
+                    break;
+                }
+                _ if transcript.turns.is_empty() => transcript
+                    .setup
+                    .parse(text)
+                    .map_err(|msg| invalid(at, &msg))?,
+                _ => return Err(invalid(at, &format!("expected a move, found {:?}", text))),
+            }
+        }
+        Ok(transcript)
+    }
+}
+
+pub(crate) fn write_header(mut out: impl Write, game: &str, setup: &Fields) -> io::Result<()> {
+    writeln!(out, "ax-transcript {}", VERSION)?;
+    writeln!(out, "game {}", game)?;
+    setup.write(out)
+}
+
+pub(crate) fn write_end(mut out: impl Write, outcome: &Outcome) -> io::Result<()> {
+    match outcome {
+        Outcome::Winner(seat) => writeln!(out, "end winner {}", seat),
+        Outcome::Draw => writeln!(out, "end draw"),
+        Outcome::Scores(scores) => {
+            write!(out, "end scores")?;
+            for score in scores {
+                write!(out, " {}", score)?;
+            }
+            writeln!(out)
+        }
+    }
+}
+
+fn read_outcome(words: &[&str]) -> Option<Outcome> {
+    match words {
+        ["winner", seat] => Some(Outcome::Winner(seat.parse().ok()?)),
+        ["draw"] => Some(Outcome::Draw),
+        ["scores", scores @ ..] => scores
+            .iter()
+            .map(|s| s.parse().ok())
+            .collect::<Option<_>>()
+            .map(Outcome::Scores),
+        _ => None,
+    }
+}
+
+fn invalid(line: usize, msg: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("line {}: {}", line, msg),
+    )
+}
@@ -0,0 +1,308 @@
+//! Step through a recorded game in the terminal.
+
+use std::fmt::Display;
+use std::io::{self, BufRead, Write};
+use std::thread;
+use std::time::Duration;
+
+use crate as ax;
+use crate::ai::Negamax;
+use crate::render::{Render, Style};
+use crate::snapshot::Position;
+use crate::transcript::{Transcript, Turn};
+use crate::{Game, Outcome, Player};
+
+/// Negamax scores a win `n` moves away as `i8::MAX - n`, so any score past
+/// this is a forced win or loss rather than a guess of the hueristic.
+const FORCED: i8 = 100;
+
+/// How good a position is for the player whose turn it is.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Evaluation<M> {
+    pub score: i8,
+    /// The move the search would play, if the game isn't over.
+    pub best: Option<M>,
+}
+
+/// Evaluates `state` by letting `search` pick a move for the player whose
+/// turn it is, which must be the seat `search` plays in.
+pub fn evaluate<E, S, F>(search: &mut Negamax<E, S, F>, state: &S) -> Evaluation<S::Move>
+where
+    E: std::fmt::Debug,
+    S: Game<E> + Clone + PartialEq,
+    F: Fn(&S) -> i8,
+{
+    if let Ok(ax::Status::Terminal(outcome)) = state.status() {
+        let score = match outcome.winner() {
+            Some(seat) if seat == state.current_player() => i8::MAX,
+            Some(_) => -i8::MAX,
+            None => 0,
+        };
+        return Evaluation { score, best: None };
+    }
+    let after = search
+        .take_turn(state.clone())
+        .unwrap_or_else(|err| panic!("negamax failed to move: {}", err));
+    Evaluation {
+        score: search.stats().score,
+        best: after.last_move(),
+    }
+}
+
+/// Returns the score of the position before the last move for the player
+/// who made it, given the score of the position after it for their opponent.
+fn from_parent(score: i8) -> i8 {
+    if score >= FORCED {
+        -(score - 1)
+    } else if score <= -FORCED {
+        -score - 1
+    } else {
+        -score
+    }
+}
+
+fn describe(score: i8) -> String {
+    if score >= FORCED {
+        format!("wins in {}", i8::MAX - score)
+    } else if score <= -FORCED {
+        format!("loses in {}", i8::MAX + score)
+    } else {
+        format!("{:+}", score)
+    }
+}
+
+/// What the viewer is asked to do.
+#[derive(Debug, PartialEq, Copy, Clone)]
+enum Command {
+    Next,
+    Back,
+    Jump(usize),
+    /// Play the rest of the game, waiting this long between moves.
+    Play(Option<Duration>),
+    Help,
+    Quit,
+}
+
+impl Command {
+    fn parse(line: &str) -> Option<Self> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        Some(match words.as_slice() {
+            [] | ["n"] | ["next"] => Command::Next,
+            ["b"] | ["back"] => Command::Back,
+            ["p"] | ["play"] => Command::Play(None),
+            ["p", ms] | ["play", ms] => {
+                Command::Play(Some(Duration::from_millis(ms.parse().ok()?)))
+            }
+            ["h"] | ["help"] | ["?"] => Command::Help,
+            ["q"] | ["quit"] => Command::Quit,
+            [n] => Command::Jump(n.parse().ok()?),
+            _ => return None,
+        })
+    }
+}
+
+const HELP: &str = "\
+Enter           show the next move
+b, back         show the previous move
+<number>        jump to the position after that many moves
+p, play [MS]    play the rest of the game, MS milliseconds per move
+q, quit         stop watching";
+
+/// Every position of a recorded game, along with how each was reached.
+pub struct Viewer<S, M> {
+    positions: Vec<S>,
+    turns: Vec<Turn<M>>,
+    outcome: Option<Outcome>,
+    evaluations: Vec<Option<Evaluation<M>>>,
+    at: usize,
+    style: Style,
+}
+
+impl<S, M> Viewer<S, M>
+where
+    S: Render,
+    M: Display + Clone + PartialEq,
+{
+    /// Plays the moves of `transcript` from the position it starts from,
+    /// checking each of them is made by the player whose turn it is and is
+    /// legal, like [`replay`](crate::combinator::replay). `new` is a new game
+    /// giving anything the transcript leaves out, see [`Transcript::start`].
+    pub fn new<E>(new: S, transcript: Transcript<M>) -> io::Result<Self>
+    where
+        S: Game<E, Move = M> + Position + Clone,
+    {
+        let mut positions = vec![transcript.start(new)?];
+        for (i, turn) in transcript.turns.iter().enumerate() {
+            let mut next = positions[i].clone();
+            let wrong = |msg: String| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("move {}: {}", i + 1, msg),
+                )
+            };
+            if turn.seat != next.current_player() {
+                return Err(wrong(format!(
+                    "seat {} moved on the turn of seat {}",
+                    turn.seat,
+                    next.current_player()
+                )));
+            }
+            if !next.is_legal(&turn.mv) {
+                return Err(wrong(format!("{} is not a legal move", turn.mv)));
+            }
+            next.apply(turn.mv.clone());
+            positions.push(next);
+        }
+        let evaluations = vec![None; positions.len()];
+        Ok(Self {
+            positions,
+            turns: transcript.turns,
+            outcome: transcript.outcome,
+            evaluations,
+            at: 0,
+            style: Style::Plain,
+        })
+    }
+
+    /// Shows positions in `style`, plain text by default.
+    pub fn style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Evaluates every position with `f`.
+    pub fn evaluate(&mut self, mut f: impl FnMut(&S) -> Evaluation<M>) {
+        self.evaluations = self.positions.iter().map(|s| Some(f(s))).collect();
+    }
+
+    /// Returns how many moves were made.
+    pub fn moves(&self) -> usize {
+        self.turns.len()
+    }
+
+    /// Shows the position after `n` moves, or the last one if there are fewer.
+    pub fn jump(&mut self, n: usize) {
+        self.at = n.min(self.moves());
+    }
+
+    pub fn next(&mut self) {
+        self.jump(self.at + 1);
+    }
+
+    pub fn back(&mut self) {
+        self.jump(self.at.saturating_sub(1));
+    }
+
+    /// Writes the current position, the move which led to it and, if the
+    /// positions were evaluated, how good that move was.
+    pub fn show(&self, mut out: impl Write) -> io::Result<()> {
+        writeln!(out)?;
+        match self.at.checked_sub(1).map(|i| &self.turns[i]) {
+            None => writeln!(out, "Start of {} moves", self.moves())?,
+            Some(turn) => writeln!(
+                out,
+                "Move {} of {}: seat {} played {} after {:?}",
+                self.at,
+                self.moves(),
+                turn.seat,
+                turn.mv,
+                turn.spent
+            )?,
+        }
+        self.positions[self.at].render_io(self.style, &mut out)?;
+
+        if self.at > 0 {
+            if let (Some(before), Some(after)) =
+                (&self.evaluations[self.at - 1], &self.evaluations[self.at])
+            {
+                let played = from_parent(after.score);
+                match &before.best {
+                    Some(best) if played < before.score => writeln!(
+                        out,
+                        "?? {} {}, but {} {}",
+                        self.turns[self.at - 1].mv,
+                        describe(played),
+                        best,
+                        describe(before.score)
+                    )?,
+                    _ => writeln!(
+                        out,
+                        "{} {}, as good as any other move",
+                        self.turns[self.at - 1].mv,
+                        describe(played)
+                    )?,
+                }
+            }
+        }
+        if self.at == self.moves() {
+            match &self.outcome {
+                Some(Outcome::Winner(seat)) => writeln!(out, "Seat {} won.", seat)?,
+                Some(Outcome::Draw) => writeln!(out, "It's a draw.")?,
+                Some(Outcome::Scores(scores)) => writeln!(out, "Final scores: {:?}", scores)?,
+                None => writeln!(out, "The game was cut short.")?,
+            }
+        } else if let Some(Evaluation {
+            score,
+            best: Some(best),
+        }) = &self.evaluations[self.at]
+        {
+            writeln!(out, "Best move {} {}", best, describe(*score))?;
+        }
+        Ok(())
+    }
+
+    /// Shows every position from the current one to the end, waiting `delay` between them.
+    pub fn play(&mut self, delay: Duration, mut out: impl Write) -> io::Result<()> {
+        while self.at < self.moves() {
+            thread::sleep(delay);
+            self.next();
+            self.show(&mut out)?;
+            out.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Lets the viewer be driven by commands read from `input`, auto-playing
+    /// at `delay` per move unless a play command says otherwise.
+    pub fn run(
+        &mut self,
+        mut input: impl BufRead,
+        mut out: impl Write,
+        delay: Duration,
+    ) -> io::Result<()> {
+        self.show(&mut out)?;
+        let mut line = String::new();
+        loop {
+            write!(out, "[{}/{}] > ", self.at, self.moves())?;
+            out.flush()?;
+            line.clear();
+            if input.read_line(&mut line)? == 0 {
+                writeln!(out)?;
+                return Ok(());
+            }
+            match Command::parse(&line) {
+                Some(Command::Next) => self.next(),
+                Some(Command::Back) => self.back(),
+                Some(Command::Jump(n)) => self.jump(n),
+                Some(Command::Play(speed)) => {
+                    self.play(speed.unwrap_or(delay), &mut out)?;
+                    continue;
+                }
+                Some(Command::Help) => {
+                    writeln!(out, "{}", HELP)?;
+                    continue;
+                }
+                Some(Command::Quit) => return Ok(()),
+                None => {
+                    writeln!(
+                        out,
+                        "Unknown command {:?}, enter help for help.",
+                        line.trim()
+                    )?;
+                    continue;
+                }
+            }
+            self.show(&mut out)?;
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use std::io;
+
+use ax::combinator::{repeat, retry, turns, Retry};
+use ax::tictactoe::Board;
+use ax::{Action, Player, PlayerError};
+
+/// An action which rejects the turn `rejections` times before placing a piece at 4.
+fn stubborn(
+    mut rejections: usize,
+    tries: &mut usize,
+) -> impl FnMut(Board<char>) -> Result<Board<char>, PlayerError<Board<char>>> + '_ {
+    move |mut board| {
+        *tries += 1;
+        if rejections > 0 {
+            rejections -= 1;
+            return Err(PlayerError::Invalid(
+                board,
+                format!("no, {} more", rejections),
+            ));
+        }
+        board.place_piece(4).unwrap();
+        Ok(board)
+    }
+}
+
+#[test]
+fn retry_asks_again_after_invalid_turns() {
+    let (mut tries, mut reasons) = (0, Vec::new());
+    let board = retry(stubborn(2, &mut tries), Retry::Forever, |r| {
+        reasons.push(r.to_string())
+    })
+    .apply(Board::new('X', 'O'))
+    .unwrap();
+
+    let mut expected = Board::new('X', 'O');
+    expected.place_piece(4).unwrap();
+    assert_eq!(board, expected);
+    assert_eq!(tries, 3);
+    assert_eq!(reasons, vec!["no, 1 more", "no, 0 more"]);
+}
+
+#[test]
+fn retry_gives_up_after_so_many_retries() {
+    let mut tries = 0;
+    let res = retry(stubborn(5, &mut tries), Retry::Times(2), |_| {}).apply(Board::new('X', 'O'));
+
+    match res {
+        Err(PlayerError::Invalid(board, reason)) => {
+            assert_eq!(board, Board::new('X', 'O'));
+            assert_eq!(reason, "no, 2 more");
+        }
+        res => panic!("expected the last rejection, got {:?}", res),
+    }
+    assert_eq!(tries, 3);
+
+    let mut tries = 0;
+    let res = retry(stubborn(5, &mut tries), Retry::Times(0), |_| {
+        panic!("nothing to report")
+    })
+    .apply(Board::new('X', 'O'));
+    assert!(matches!(res, Err(PlayerError::Invalid(..))));
+    assert_eq!(tries, 1);
+}
+
+#[test]
+fn retry_passes_io_errors_through() {
+    let mut tries = 0;
+    let closed = |_: Board<char>| -> Result<Board<char>, PlayerError<Board<char>>> {
+        tries += 1;
+        Err(io::Error::from(io::ErrorKind::UnexpectedEof).into())
+    };
+    let res =
+        retry(closed, Retry::Forever, |_| panic!("nothing to report")).apply(Board::new('X', 'O'));
+
+    match res {
+        Err(PlayerError::Io(err)) => assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof),
+        res => panic!("expected an io error, got {:?}", res),
+    }
+    assert_eq!(tries, 1);
+}
+
+/// Places its pieces at the given positions, in order.
+struct Scripted(Vec<usize>);
+
+impl Player<Board<char>> for Scripted {
+    fn take_turn(
+        &mut self,
+        mut board: Board<char>,
+    ) -> Result<Board<char>, PlayerError<Board<char>>> {
+        board.place_piece(self.0.remove(0)).unwrap();
+        Ok(board)
+    }
+}
+
+#[test]
+fn turns_asks_the_player_in_the_current_seat() {
+    let players = vec![Scripted(vec![0, 1, 2]), Scripted(vec![3, 4])];
+    let board = repeat(turns(players), 5)
+        .apply(Board::new('X', 'O'))
+        .unwrap();
+    let mut expected = Board::new('X', 'O');
+    for mv in &[0, 3, 1, 4, 2] {
+        expected.place_piece(*mv).unwrap();
+    }
+    assert_eq!(board, expected);
+    assert_eq!(board[3], 'O');
+
+    // Resuming a game with O to move starts with the second player.
+    let mut board = Board::new('X', 'O');
+    board.place_piece(8).unwrap();
+    let players = vec![Scripted(vec![0]), Scripted(vec![4])];
+    let board = repeat(turns(players), 2).apply(board).unwrap();
+    assert_eq!(board[4], 'O');
+    assert_eq!(board[0], 'X');
+}
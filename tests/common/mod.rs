@@ -0,0 +1,12 @@
+//! Fixtures shared by the integration tests.
+
+use ax::tictactoe::Board;
+
+/// Returns a board after `moves` were played in order, with `X` going first.
+pub fn board(moves: &[usize]) -> Board<char> {
+    let mut board = Board::new('X', 'O');
+    for mv in moves {
+        board.place_piece(*mv).expect("position should be legal");
+    }
+    board
+}
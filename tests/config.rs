@@ -0,0 +1,105 @@
+use ax::ai::nn::Loss;
+use ax::config::{self, Config, Error};
+
+#[test]
+fn the_default_file_holds_the_defaults() {
+    let config: Config = config::DEFAULT.parse().unwrap();
+    assert_eq!(config, Config::default());
+}
+
+#[test]
+fn keys_override_the_defaults() {
+    let config: Config = "
+# a comment
+seed: 42
+negamax:
+  depth: 4 # shallow
+tic-tac-toe:
+  ai: [mcts, 'my model.model']
+train:
+  loss: cross-entropy
+  layers: [18, 9]
+"
+    .parse()
+    .unwrap();
+
+    assert_eq!(config.seed, Some(42));
+    assert_eq!(config.negamax.depth, 4);
+    assert_eq!(config.tic_tac_toe.ai, vec!["mcts", "my model.model"]);
+    assert_eq!(config.train.loss, Loss::CrossEntropy);
+    assert_eq!(config.train.layers, vec![18, 9]);
+    assert_eq!(config.train.epochs, Config::default().train.epochs);
+}
+
+#[test]
+fn unknown_keys_are_rejected() {
+    let err = "mcts:\n  iterations: 10\n  iteration: 10\n"
+        .parse::<Config>()
+        .unwrap_err();
+    assert!(
+        matches!(err, Error::UnknownKey { line: 3, .. }),
+        "{:?}",
+        err
+    );
+    assert_eq!(err.to_string(), "line 3: unknown key \"mcts.iteration\"");
+}
+
+#[test]
+fn invalid_values_are_rejected() {
+    for text in &[
+        "train:\n  epochs: -1\n",
+        "train:\n  optimizer: rmsprop\n",
+        "tic-tac-toe:\n  ai: [a, b, c]\n",
+        "number-guesser:\n  low: 10\n  high: 10\n",
+        "mcts:\n  iterations: 0\n",
+        "mcts:\n  exploration: -5\n",
+        "train:\n  learning-rate: NaN\n",
+        "train:\n  learning-rate: -0.1\n",
+        "train:\n  population: 0\n",
+        "train:\n  layers: [8, 0]\n",
+    ] {
+        let err = text.parse::<Config>().unwrap_err();
+        assert!(
+            matches!(err, Error::Invalid { line: 2..=3, .. }),
+            "{:?}",
+            err
+        );
+    }
+}
+
+#[test]
+fn unknown_sections_are_rejected() {
+    let err = "negamax:\n  depth: 4\nfoo:\n"
+        .parse::<Config>()
+        .unwrap_err();
+    assert!(
+        matches!(err, Error::UnknownSection { line: 3, .. }),
+        "{:?}",
+        err
+    );
+    assert!(err
+        .to_string()
+        .starts_with("line 3: unknown section \"foo\""));
+}
+
+#[test]
+fn yaml_outside_the_subset_is_rejected() {
+    for text in &[
+        "tic-tac-toe:\n  ai:\n    - random\n",
+        "tic-tac-toe:\n- random\n",
+        "train:\n\tepochs: 4\n",
+        "tic-tac-toe:\n  model: |\n",
+        "negamax: {depth: 4}\n",
+        "---\n",
+        "seed:\n",
+    ] {
+        let err = text.parse::<Config>().unwrap_err();
+        assert!(matches!(err, Error::Syntax { .. }), "{:?}: {:?}", text, err);
+    }
+}
+
+#[test]
+fn indented_keys_need_a_section() {
+    let err = "  depth: 4\n".parse::<Config>().unwrap_err();
+    assert!(matches!(err, Error::Syntax { line: 1, .. }), "{:?}", err);
+}
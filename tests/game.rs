@@ -0,0 +1,43 @@
+use ax::numberguesser;
+use ax::tictactoe::Board;
+use ax::{FiniteState, Game, StateHash};
+
+#[test]
+fn undo_takes_back_the_last_move() {
+    let mut board = Board::new('X', 'O');
+    board.apply(4);
+    let before = board.clone();
+    board.apply(0);
+    board.undo(0);
+    assert_eq!(board, before);
+    assert_eq!(board.state_hash(), before.state_hash());
+
+    let mut state = numberguesser::State::with_number(0, 10, 7);
+    state.apply(3);
+    let before = state.clone();
+    state.apply(5);
+    state.undo(5);
+    assert_eq!(state, before);
+}
+
+#[test]
+fn games_list_a_next_state_for_each_legal_move() {
+    let mut board = Board::new('X', 'O');
+    board.apply(4);
+    let next = board.next_possibilities().unwrap();
+    assert_eq!(next.len(), 8);
+    for (mv, state) in board.legal_moves().into_iter().zip(&next) {
+        let mut expected = board.clone();
+        expected.apply(mv);
+        assert_eq!(*state, expected);
+    }
+}
+
+#[test]
+fn finished_games_have_no_next_states() {
+    let mut board = Board::new('X', 'O');
+    for mv in &[0, 3, 1, 4, 2] {
+        board.apply(*mv);
+    }
+    assert_eq!(board.next_possibilities(), None);
+}
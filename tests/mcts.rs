@@ -0,0 +1,41 @@
+mod common;
+
+use std::time::Duration;
+
+use ax::ai::{Budget, Mcts};
+use ax::Player;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use common::board;
+
+#[test]
+fn takes_a_win() {
+    let mut ai = Mcts::new(StdRng::seed_from_u64(7)).budget(Budget::Nodes(2_000));
+
+    let board = ai.take_turn(board(&[0, 3, 1, 4])).unwrap();
+
+    assert_eq!(board.winner(), Some(&'X'));
+    assert_eq!(ai.iterations(), 2_000);
+}
+
+#[test]
+fn blocks_a_loss() {
+    let mut ai = Mcts::new(StdRng::seed_from_u64(7)).budget(Budget::Nodes(2_000));
+
+    let board = ai.take_turn(board(&[0, 4, 1])).unwrap();
+
+    assert_eq!(board[2], 'O');
+}
+
+#[test]
+fn moves_even_without_a_budget() {
+    for budget in &[Budget::Nodes(0), Budget::Time(Duration::from_secs(0))] {
+        let mut ai = Mcts::new(StdRng::seed_from_u64(7)).budget(*budget);
+
+        let board = ai.take_turn(board(&[0, 4])).unwrap();
+
+        assert_eq!(board.moves().len(), 3);
+        assert_eq!(ai.iterations(), 1);
+    }
+}
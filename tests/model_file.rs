@@ -0,0 +1,125 @@
+use ax::ai::file::{Error, ModelFile, Trained, VERSION};
+use ax::ai::nn::Network;
+use ax::ai::rnn::Recurrent;
+use ax::numberguesser::State;
+use ax::seed::Seed;
+use ax::train;
+use ax::Player;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+fn written(file: &ModelFile) -> String {
+    let mut out = Vec::new();
+    file.write(&mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn model_files_read_what_they_write() {
+    let mut rng = StdRng::seed_from_u64(7);
+    let file = ModelFile::new(
+        "number-guesser",
+        Trained::Recurrent(Recurrent::new(3, 4, 1, &mut rng)),
+    )
+    .meta("algorithm", "backprop")
+    .meta("result", "loss 0.1, average guesses 6.5");
+
+    let read = ModelFile::read(written(&file).as_bytes()).unwrap();
+    assert_eq!(read, file);
+    assert_eq!(read.get("result"), Some("loss 0.1, average guesses 6.5"));
+}
+
+#[test]
+fn other_versions_are_rejected() {
+    let mut rng = StdRng::seed_from_u64(7);
+    let file = ModelFile::new(
+        "tic-tac-toe",
+        Trained::Network(Network::new(&[18, 1], &mut rng)),
+    );
+    let text = written(&file).replacen(
+        &format!("ax-model {}", VERSION),
+        &format!("ax-model {}", VERSION + 1),
+        1,
+    );
+
+    match ModelFile::read(text.as_bytes()) {
+        Err(Error::Version(v)) => assert_eq!(v, VERSION + 1),
+        other => panic!("expected a version error, got {:?}", other),
+    }
+}
+
+#[test]
+fn other_games_are_rejected() {
+    let mut rng = StdRng::seed_from_u64(7);
+    let file = ModelFile::new(
+        "tic-tac-toe",
+        Trained::Network(Network::new(&[18, 1], &mut rng)),
+    );
+
+    let err = file.for_game("number-guesser").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "model was trained for tic-tac-toe, not number-guesser"
+    );
+}
+
+#[test]
+fn files_without_a_header_are_rejected() {
+    let mut rng = StdRng::seed_from_u64(7);
+    let mut out = Vec::new();
+    Network::new(&[18, 1], &mut rng).write(&mut out).unwrap();
+
+    assert!(matches!(ModelFile::read(&out[..]), Err(Error::Format(_))));
+}
+
+#[test]
+fn models_must_fit_their_game() {
+    let dir = std::env::temp_dir().join(format!("ax-model-file-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("tic-tac-toe.model");
+    let mut rng = StdRng::seed_from_u64(7);
+    let mut load = |sizes: &[usize]| {
+        let file = ModelFile::new(
+            "tic-tac-toe",
+            Trained::Network(Network::new(sizes, &mut rng)),
+        );
+        std::fs::write(&path, written(&file)).unwrap();
+        train::load_tic_tac_toe::<char>(&path, Seed::new(Some(7)).stream("test"))
+            .map(|_| ())
+            .map_err(|err| err.to_string())
+    };
+
+    assert_eq!(load(&[18, 9, 1]), Ok(()));
+    let err = load(&[3, 1]).unwrap_err();
+    assert!(
+        err.ends_with("not a valid model file: the model takes 3 inputs, but the game gives it 18"),
+        "{}",
+        err
+    );
+    let err = load(&[18, 0]).unwrap_err();
+    assert!(err.ends_with("the model has no outputs"), "{}", err);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn network_guessers_cope_with_wide_ranges() {
+    let dir = std::env::temp_dir().join(format!("ax-wide-guesser-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("number-guesser.model");
+    let mut rng = StdRng::seed_from_u64(7);
+    let file = ModelFile::new(
+        "number-guesser",
+        Trained::Network(Network::new(&[3, 4, 1], &mut rng)),
+    );
+    std::fs::write(&path, written(&file)).unwrap();
+
+    let mut player = train::load_guesser(&path, Seed::new(Some(7)).stream("test")).unwrap();
+    let state = player
+        .take_turn(State::with_number(0, 1_000_000_000, 5))
+        .unwrap();
+    let guess = state.last_guess().unwrap();
+    assert!((0..1_000_000_000).contains(&guess), "{}", guess);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
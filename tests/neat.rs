@@ -0,0 +1,56 @@
+use ax::ai::neat::{Config, Genome, Population};
+use ax::ai::Model;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+fn evolved(generations: usize) -> Population {
+    let mut rng = StdRng::seed_from_u64(7);
+    let config = Config {
+        population: 50,
+        add_connection: 0.3,
+        add_node: 0.2,
+        ..Default::default()
+    };
+    let mut population = Population::new(2, 1, config, &mut rng);
+    for _ in 0..generations {
+        // Reward networks for telling their two inputs apart.
+        population.evolve(
+            |genome| {
+                let net = genome.network();
+                2.0 + net.forward(&[1.0, 0.0])[0] - net.forward(&[0.0, 1.0])[0]
+            },
+            &mut rng,
+        );
+    }
+    population
+}
+
+#[test]
+fn evolving_keeps_the_population_size() {
+    let population = evolved(20);
+    assert_eq!(population.genomes().len(), 50);
+    for genome in population.genomes() {
+        assert_eq!(genome.network().forward(&[0.5, 0.5]).len(), 1);
+    }
+}
+
+#[test]
+fn genomes_read_what_they_write() {
+    let population = evolved(20);
+    for genome in population.genomes() {
+        let mut out = Vec::new();
+        genome.write(&mut out).unwrap();
+        assert_eq!(&Genome::read(&out[..]).unwrap(), genome);
+    }
+}
+
+#[test]
+fn champion_improves() {
+    let first = evolved(1);
+    let last = evolved(30);
+    let score = |p: &Population| {
+        let net = p.champion().unwrap().network();
+        net.forward(&[1.0, 0.0])[0] - net.forward(&[0.0, 1.0])[0]
+    };
+    assert!(score(&last) >= score(&first));
+}
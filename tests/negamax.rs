@@ -0,0 +1,129 @@
+mod common;
+
+use ax::ai::{Budget, Negamax};
+use ax::tictactoe::Board;
+use ax::{Player, State};
+
+use common::board;
+
+const POSITIONS: &[&[usize]] = &[
+    &[],
+    &[4],
+    &[0],
+    &[0, 4],
+    &[4, 0, 8],
+    &[0, 1, 3],
+    &[1, 4, 7, 3],
+    &[0, 4, 8, 2, 6],
+    &[2, 4, 6, 1],
+];
+
+type Hueristic = fn(&Board<char>) -> i8;
+
+fn negamax(board: &Board<char>) -> Negamax<(), Board<char>, Hueristic> {
+    Negamax::with_hueristic(board.current_player(), 9, |_| 0)
+}
+
+#[test]
+fn pruning_picks_the_same_move() {
+    for moves in POSITIONS {
+        let mut plain = negamax(&board(moves)).pruning(false);
+        let mut pruned = negamax(&board(moves));
+
+        let expected = plain.take_turn(board(moves)).unwrap();
+        let actual = pruned.take_turn(board(moves)).unwrap();
+
+        assert_eq!(expected, actual, "after moves {:?}", moves);
+        assert_eq!(plain.stats().score, pruned.stats().score);
+        assert!(pruned.stats().nodes <= plain.stats().nodes);
+    }
+}
+
+#[test]
+fn ordering_does_not_change_the_score() {
+    for moves in POSITIONS {
+        let mut plain = negamax(&board(moves)).pruning(false);
+        let mut ordered = negamax(&board(moves)).order_moves(|_, moves| moves.reverse());
+
+        plain.take_turn(board(moves)).unwrap();
+        ordered.take_turn(board(moves)).unwrap();
+
+        assert_eq!(plain.stats().score, ordered.stats().score);
+    }
+}
+
+#[test]
+fn iterative_deepening_takes_a_win() {
+    let mut ai = negamax(&board(&[0, 3, 1, 4])).budget(Budget::Nodes(50));
+
+    let board = ai.take_turn(board(&[0, 3, 1, 4])).unwrap();
+
+    assert_eq!(board.winner(), Some(&'X'));
+    assert!(ai.stats().nodes <= 50);
+}
+
+#[test]
+fn transposition_table_keeps_the_score() {
+    for moves in POSITIONS {
+        let mut plain = negamax(&board(moves));
+        let mut cached = negamax(&board(moves)).transposition_table(1 << 12);
+
+        plain.take_turn(board(moves)).unwrap();
+        cached.take_turn(board(moves)).unwrap();
+
+        assert_eq!(plain.stats().score, cached.stats().score);
+        assert!(cached.stats().nodes <= plain.stats().nodes);
+    }
+
+    let mut ai = negamax(&board(&[])).transposition_table(1 << 12);
+    ai.take_turn(board(&[])).unwrap();
+    assert!(ai.stats().tt_hits > 0);
+    assert_eq!(ai.table().unwrap().hits(), ai.stats().tt_hits);
+}
+
+#[test]
+fn iterative_deepening_looks_past_table_hits() {
+    let mut ai = negamax(&board(&[]))
+        .transposition_table(1 << 12)
+        .budget(Budget::Nodes(5000));
+
+    // The second search finds the states of the first one in the table, but
+    // they were only searched so deep, so it has to keep deepening.
+    ai.take_turn(board(&[])).unwrap();
+    let depth = ai.stats().depth;
+    ai.take_turn(board(&[])).unwrap();
+
+    assert!(depth > 1, "{}", depth);
+    assert!(
+        ai.stats().depth >= depth,
+        "{} < {}",
+        ai.stats().depth,
+        depth
+    );
+}
+
+#[test]
+fn transpositions_hash_the_same() {
+    use ax::{Game, StateHash};
+
+    let mut board = board(&[0, 4, 8]);
+    let hash = board.state_hash();
+
+    board.apply(2);
+    assert_ne!(board.state_hash(), hash);
+    board.undo(2);
+    assert_eq!(board.state_hash(), hash);
+
+    assert_eq!(self::board(&[8, 4, 0]).state_hash(), hash);
+}
+
+#[test]
+fn shallow_search_with_the_hueristic_blocks_a_win() {
+    let moves = [0, 4, 1];
+    let seat = board(&moves).current_player();
+    let mut ai = Negamax::with_hueristic(seat, 2, move |b: &Board<char>| b.hueristic(seat));
+
+    let board = ai.take_turn(board(&moves)).unwrap();
+
+    assert_eq!(board[2], 'O');
+}
@@ -0,0 +1,54 @@
+use ax::ai::nn::{Activation, Loss, Network, Optimizer};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+const XOR: [([f64; 2], f64); 4] = [
+    ([0.0, 0.0], 0.0),
+    ([0.0, 1.0], 1.0),
+    ([1.0, 0.0], 1.0),
+    ([1.0, 1.0], 0.0),
+];
+
+/// Trains a network on XOR and returns its largest error.
+fn learn_xor(net: Network) -> f64 {
+    let mut net = net;
+    for _ in 0..5000 {
+        for (input, target) in &XOR {
+            net.train(input, &[*target]);
+        }
+    }
+    XOR.iter()
+        .map(|(input, target)| (net.forward(input)[0] - target).abs())
+        .fold(0.0, f64::max)
+}
+
+#[test]
+fn sgd_learns_xor() {
+    let mut rng = StdRng::seed_from_u64(7);
+    let net = Network::new(&[2, 4, 1], &mut rng).optimizer(Optimizer::sgd(0.1));
+    assert!(learn_xor(net) < 0.2);
+}
+
+#[test]
+fn adam_learns_xor_with_cross_entropy() {
+    let mut rng = StdRng::seed_from_u64(7);
+    let net = Network::new(&[2, 8, 1], &mut rng)
+        .activations(Activation::Relu, Activation::Sigmoid)
+        .loss(Loss::CrossEntropy)
+        .optimizer(Optimizer::adam(0.01));
+    assert!(learn_xor(net) < 0.2);
+}
+
+#[test]
+fn networks_read_what_they_write() {
+    let mut rng = StdRng::seed_from_u64(7);
+    let net = Network::new(&[3, 5, 2], &mut rng).activations(Activation::Relu, Activation::Linear);
+
+    let mut out = Vec::new();
+    net.write(&mut out).unwrap();
+    let read = Network::read(&out[..]).unwrap();
+
+    let input = [0.1, -0.4, 0.9];
+    assert_eq!(read.forward(&input), net.forward(&input));
+    assert_eq!(read.to_string(), "3-5-2");
+}
@@ -0,0 +1,62 @@
+use ax::combinator::{repeat_until_terminal, take_turn};
+use ax::numberguesser::{BinarySearch, Hint, RandomInRange, State, MAX_MOVES};
+use ax::{Action, Game, Player};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+fn play(player: impl Player<State>, number: i64) -> State {
+    let mut run = repeat_until_terminal(take_turn(player));
+    run.apply(State::with_number(0, 100, number)).unwrap()
+}
+
+#[test]
+fn binary_search_needs_at_most_seven_guesses() {
+    for number in 0..100 {
+        let state = play(BinarySearch, number);
+        assert_eq!(state.last_guess(), Some(number));
+        assert!(
+            state.history().len() <= 7,
+            "{} took {:?}",
+            number,
+            state.history()
+        );
+    }
+}
+
+#[test]
+fn random_guesses_stay_in_the_remaining_range() {
+    let mut rng = StdRng::seed_from_u64(7);
+    for number in &[0, 42, 99] {
+        let state = play(RandomInRange::new(&mut rng), *number);
+        let history = state.history();
+        assert_eq!(history.last(), Some(&(*number, Hint::Correct)));
+
+        let guesses: Vec<i64> = history.iter().map(|(g, _)| *g).collect();
+        let mut sorted = guesses.clone();
+        sorted.sort_unstable();
+        sorted.dedup();
+        assert_eq!(sorted.len(), guesses.len(), "guessed twice: {:?}", guesses);
+    }
+}
+
+#[test]
+fn legal_moves_are_the_numbers_left() {
+    let mut state = State::with_number(0, 100, 42);
+    state.apply(30);
+    state.apply(50);
+    assert_eq!(state.legal_moves(), (31..50).collect::<Vec<_>>());
+    assert!(state.is_legal(&10));
+
+    state.apply(42);
+    assert!(state.legal_moves().is_empty());
+}
+
+#[test]
+fn wide_ranges_list_a_spread_of_moves() {
+    let state = State::with_number(i64::MIN, i64::MAX, 0);
+    let moves = state.legal_moves();
+    assert_eq!(moves.len(), MAX_MOVES as usize);
+    assert_eq!(moves.first(), Some(&i64::MIN));
+    assert_eq!(moves.last(), Some(&(i64::MAX - 1)));
+    assert!(moves.windows(2).all(|w| w[0] < w[1]));
+}
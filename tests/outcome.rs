@@ -0,0 +1,20 @@
+use ax::Outcome;
+
+#[test]
+fn winners_and_draws() {
+    assert_eq!(Outcome::Winner(1).winner(), Some(1));
+    assert_eq!(Outcome::Draw.winner(), None);
+}
+
+#[test]
+fn a_single_top_score_wins() {
+    assert_eq!(Outcome::Scores(vec![3, 7, 5]).winner(), Some(1));
+    assert_eq!(Outcome::Scores(vec![-2]).winner(), Some(0));
+}
+
+#[test]
+fn tied_or_missing_scores_have_no_winner() {
+    assert_eq!(Outcome::Scores(vec![7, 3, 7]).winner(), None);
+    assert_eq!(Outcome::Scores(vec![0, 0]).winner(), None);
+    assert_eq!(Outcome::Scores(vec![]).winner(), None);
+}
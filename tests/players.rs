@@ -0,0 +1,42 @@
+use std::path::PathBuf;
+
+use ax::players::{self, Options};
+use ax::seed::{Seed, Streams};
+
+fn options() -> Options {
+    Options {
+        depth: 2,
+        iterations: 10,
+        exploration: 1.0,
+        model: PathBuf::from("tic-tac-toe.model"),
+        streams: Streams::new(Seed::new(Some(1))),
+    }
+}
+
+#[test]
+fn every_named_ai_can_be_created() {
+    for name in &["human", "random", "negamax", "mcts"] {
+        assert!(players::tic_tac_toe::<char>(name, 0, &options()).is_ok());
+    }
+}
+
+#[test]
+fn typos_are_not_taken_for_model_files() {
+    let err = players::tic_tac_toe::<char>("negamx", 0, &options())
+        .err()
+        .unwrap()
+        .to_string();
+    assert_eq!(
+        err,
+        "unknown AI \"negamx\", expected one of human, random, negamax, mcts, nn, neatnn \
+         or the path of a model file"
+    );
+
+    for path in &["missing.model", "models/missing"] {
+        let err = players::tic_tac_toe::<char>(path, 0, &options())
+            .err()
+            .unwrap()
+            .to_string();
+        assert!(err.starts_with(&format!("{}: ", path)), "{}", err);
+    }
+}
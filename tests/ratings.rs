@@ -0,0 +1,112 @@
+use std::path::PathBuf;
+
+use ax::players::Options;
+use ax::ratings::{self, Ledger};
+use ax::seed::{Seed, Streams};
+use ax::tournament::{Pairing, Record};
+
+const WIN: Record = Record {
+    wins: 1,
+    draws: 0,
+    losses: 0,
+};
+const DRAW: Record = Record {
+    wins: 0,
+    draws: 1,
+    losses: 0,
+};
+
+fn pairing(ai: &str, opponent: &str, games: Vec<Record>) -> Pairing {
+    let mut record = Record::default();
+    for game in &games {
+        record.wins += game.wins;
+        record.draws += game.draws;
+        record.losses += game.losses;
+    }
+    Pairing {
+        ai: ai.to_string(),
+        opponent: opponent.to_string(),
+        record,
+        games,
+    }
+}
+
+fn identities(names: &[&str]) -> Vec<(String, String)> {
+    names
+        .iter()
+        .map(|n| (n.to_string(), format!("id:{}", n.replace(' ', "-"))))
+        .collect()
+}
+
+#[test]
+fn winners_gain_what_losers_lose() {
+    let mut ledger = Ledger::default();
+    ledger.record(
+        &[pairing("strong", "weak", vec![WIN, WIN, DRAW, WIN])],
+        &identities(&["strong", "weak"]),
+    );
+
+    let entries = ledger.entries();
+    assert_eq!(entries[0].name, "strong");
+    assert_eq!(entries[0].games, 4);
+    assert!(entries[0].elo > 1500.0 && entries[0].glicko.rating > 1500.0);
+    assert!(entries[1].elo < 1500.0 && entries[1].glicko.rating < 1500.0);
+    assert!((entries[0].elo + entries[1].elo - 3000.0).abs() < 1e-9);
+    assert!(entries[0].glicko.deviation < 350.0);
+}
+
+#[test]
+fn players_keep_their_identity_across_names() {
+    let mut ledger = Ledger::default();
+    let ids = vec![
+        ("a".to_string(), "model:1".to_string()),
+        ("b".to_string(), "random".to_string()),
+        ("renamed".to_string(), "model:1".to_string()),
+    ];
+    ledger.record(&[pairing("a", "b", vec![WIN])], &ids);
+    ledger.record(&[pairing("renamed", "b", vec![WIN])], &ids);
+
+    let entries = ledger.entries();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].name, "renamed");
+    assert_eq!(entries[0].games, 2);
+}
+
+#[test]
+fn ledgers_read_what_they_write() {
+    let mut ledger = Ledger::default();
+    ledger.record(
+        &[pairing("x", "a name with spaces", vec![DRAW, WIN])],
+        &identities(&["x", "a name with spaces"]),
+    );
+
+    let mut out = Vec::new();
+    ledger.write(&mut out).unwrap();
+    assert_eq!(Ledger::read(&out[..]).unwrap(), ledger);
+    assert!(Ledger::read(&b"player x 1 2 3 4 5 x\n"[..]).is_err());
+}
+
+#[test]
+fn identities_include_settings_and_model_contents() {
+    let dir = std::env::temp_dir().join(format!("ax-ratings-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let (first, second) = (dir.join("first.model"), dir.join("second.model"));
+    std::fs::write(&first, "one").unwrap();
+    std::fs::write(&second, "two").unwrap();
+
+    let options = |depth| Options {
+        depth,
+        iterations: 100,
+        exploration: 1.0,
+        model: PathBuf::from(&first),
+        streams: Streams::new(Seed::new(Some(1))),
+    };
+    let id = |name: &str, depth| ratings::identity(name, &options(depth)).unwrap();
+
+    assert_ne!(id("negamax", 3), id("negamax", 9));
+    assert_eq!(id("nn", 9), id(first.to_str().unwrap(), 3));
+    assert_ne!(id("nn", 9), id(second.to_str().unwrap(), 9));
+    assert!(ratings::identity("missing.model", &options(9)).is_err());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,82 @@
+mod common;
+
+use std::io;
+
+use ax::combinator::{render, Either};
+use ax::numberguesser;
+use ax::render::{ansi, Render, Style};
+use ax::tictactoe::Board;
+use ax::Action;
+
+use common::board;
+
+#[test]
+fn plain_and_compact_boards() {
+    let board = board(&[4, 0, 8]);
+    assert_eq!(board.to_rendered(Style::Plain), board.to_string());
+    assert_eq!(board.to_rendered(Style::Compact), "O../.X./..X\n");
+}
+
+#[test]
+fn ansi_boards_highlight_the_last_move_and_winning_line() {
+    let playing = board(&[4, 0, 8]).to_rendered(Style::Ansi);
+    assert_eq!(playing.matches(ansi::UNDERLINE).count(), 1);
+    assert!(!playing.contains(ansi::ON_GREEN));
+
+    let won = board(&[0, 3, 1, 4, 2]).to_rendered(Style::Ansi);
+    assert_eq!(won.matches(ansi::ON_GREEN).count(), 3);
+}
+
+#[test]
+fn json_is_valid() {
+    let json = board(&[0, 3, 1, 4, 2]).to_rendered(Style::Json);
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(value["cells"][3], "O");
+    assert_eq!(value["cells"][8], serde_json::Value::Null);
+    assert_eq!(value["winner"], 0);
+    assert_eq!(value["line"], serde_json::json!([0, 1, 2]));
+
+    let mut state = numberguesser::State::with_number(0, 100, 37);
+    state.guess(50).unwrap();
+    let json = state.to_rendered(Style::Json);
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(value["hint"], "too-high");
+    assert_eq!(value["guesses"], serde_json::json!([50]));
+}
+
+#[test]
+fn every_style_writes_whole_lines() {
+    let either: Either<Board<char>, numberguesser::State> =
+        Either::Right(numberguesser::State::with_number(0, 10, 3));
+    for style in &[Style::Plain, Style::Ansi, Style::Compact, Style::Json] {
+        assert!(board(&[4]).to_rendered(*style).ends_with('\n'));
+        assert!((0, board(&[])).to_rendered(*style).ends_with('\n'));
+        assert!(either.to_rendered(*style).ends_with('\n'));
+    }
+}
+
+struct Broken;
+
+impl io::Write for Broken {
+    fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+        Err(io::Error::new(io::ErrorKind::BrokenPipe, "closed"))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn io_errors_are_passed_on() {
+    let mut out = Vec::new();
+    render::<(), _>(&mut out, Style::Compact)
+        .apply(board(&[4]))
+        .unwrap();
+    assert_eq!(out, b".../.X./...\n");
+
+    let err = render::<(), _>(Broken, Style::Plain)
+        .apply(board(&[4]))
+        .unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+}
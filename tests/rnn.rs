@@ -0,0 +1,43 @@
+use ax::ai::nn::Optimizer;
+use ax::ai::rnn::Recurrent;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// A sequence which starts with a sign followed by zeros, where the
+/// output should remember the sign until the end.
+fn remember(sign: f64, len: usize) -> (Vec<Vec<f64>>, Vec<Vec<f64>>) {
+    let mut inputs = vec![vec![sign]];
+    inputs.resize(len, vec![0.0]);
+    (inputs, vec![vec![sign * 0.8]; len])
+}
+
+#[test]
+fn remembers_the_first_input() {
+    let mut rng = StdRng::seed_from_u64(7);
+    let mut net = Recurrent::new(1, 8, 1, &mut rng).optimizer(Optimizer::adam(0.01));
+    for _ in 0..2000 {
+        let sign = if rng.gen_bool(0.5) { 1.0 } else { -1.0 };
+        let (inputs, targets) = remember(sign, 6);
+        net.train(&inputs, &targets);
+    }
+
+    for sign in &[1.0, -1.0] {
+        let (inputs, _) = remember(*sign, 6);
+        let last = net.run(&inputs).pop().unwrap()[0];
+        assert!(last * sign > 0.5, "forgot {} by the end: {}", sign, last);
+    }
+}
+
+#[test]
+fn networks_read_what_they_write() {
+    let mut rng = StdRng::seed_from_u64(7);
+    let net = Recurrent::new(3, 5, 2, &mut rng);
+
+    let mut out = Vec::new();
+    net.write(&mut out).unwrap();
+    let read = Recurrent::read(&out[..]).unwrap();
+
+    let inputs = vec![vec![0.1, -0.4, 0.9], vec![1.0, 0.0, 0.0]];
+    assert_eq!(read.run(&inputs), net.run(&inputs));
+    assert_eq!(read, net);
+}
@@ -0,0 +1,42 @@
+use ax::seed::{self, Seed};
+use rand::Rng;
+
+fn draw(seed: Seed, name: &str) -> Vec<u64> {
+    let mut rng = seed.stream(name);
+    (0..8).map(|_| rng.gen()).collect()
+}
+
+#[test]
+fn streams_repeat_for_the_same_seed() {
+    let seed = Seed::new(Some(42));
+    assert_eq!(
+        draw(seed, "tic-tac-toe seat 0"),
+        draw(seed, "tic-tac-toe seat 0")
+    );
+    assert_eq!(seed, Seed::new(Some(42)));
+}
+
+#[test]
+fn streams_are_independent() {
+    let seed = Seed::new(Some(42));
+    assert_ne!(
+        draw(seed, "tic-tac-toe seat 0"),
+        draw(seed, "tic-tac-toe seat 1")
+    );
+    assert_ne!(draw(seed, "train"), draw(Seed::new(Some(43)), "train"));
+}
+
+#[test]
+fn derived_seeds_repeat_and_differ() {
+    let seed = Seed::new(Some(42));
+    assert_eq!(seed.derive("game 1"), seed.derive("game 1"));
+    assert_ne!(seed.derive("game 1"), seed.derive("game 2"));
+    assert_ne!(draw(seed.derive("game 1"), "train"), draw(seed, "train"));
+}
+
+#[test]
+fn fnv1a_matches_the_reference() {
+    assert_eq!(seed::fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+    assert_eq!(seed::fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+    assert_eq!(seed::fnv1a(b"foobar"), 0x8594_4171_f739_67e8);
+}
@@ -0,0 +1,102 @@
+#![cfg(feature = "serde")]
+
+use std::fmt::Debug;
+use std::time::{Duration, UNIX_EPOCH};
+
+use ax::combinator::Either;
+use ax::numberguesser;
+use ax::tictactoe::Board;
+use ax::transcript::{Transcript, Turn};
+use ax::{Game, Outcome, State, StateHash, Status};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+fn round_trip<T>(value: &T) -> T
+where
+    T: Serialize + DeserializeOwned + PartialEq + Debug,
+{
+    let json = serde_json::to_string(value).unwrap();
+    let back: T = serde_json::from_str(&json).unwrap();
+    assert_eq!(&back, value, "{}", json);
+    back
+}
+
+#[test]
+fn statuses_and_outcomes_round_trip() {
+    round_trip(&Status::Valid);
+    round_trip(&Status::Terminal(Outcome::Winner(1)));
+    round_trip(&Status::Terminal(Outcome::Draw));
+    round_trip(&Status::Terminal(Outcome::Scores(vec![3, -2])));
+}
+
+#[test]
+fn eithers_round_trip() {
+    round_trip(&Either::<Status, i64>::Left(Status::Valid));
+    round_trip(&Either::<Status, i64>::Right(7));
+}
+
+#[test]
+fn boards_round_trip_and_keep_playing() {
+    let mut board = Board::new('X', 'O');
+    for mv in &[4, 0, 8] {
+        board.apply(*mv);
+    }
+    let mut back = round_trip(&board);
+    assert_eq!(back.state_hash(), board.state_hash());
+    assert_eq!(back.current_player(), 1);
+
+    back.apply(2);
+    board.apply(2);
+    assert_eq!(back, board);
+    assert_eq!(
+        serde_json::to_string(&board).unwrap(),
+        r#"{"players":["X","O"],"moves":[4,0,8,2]}"#
+    );
+}
+
+#[test]
+fn only_legal_boards_are_deserialized() {
+    for (json, reason) in &[
+        (
+            r#"{"players":["X","O"],"moves":[4,4]}"#,
+            "move 2 at 4 is not legal",
+        ),
+        (
+            r#"{"players":["X","O"],"moves":[9]}"#,
+            "move 1 at 9 is not legal",
+        ),
+        (
+            r#"{"players":["X","X"],"moves":[]}"#,
+            "the players need pieces of their own",
+        ),
+    ] {
+        let err = serde_json::from_str::<Board<char>>(json).unwrap_err();
+        assert!(err.to_string().contains(reason), "{}", err);
+    }
+}
+
+#[test]
+fn number_guesser_states_round_trip() {
+    let mut state = numberguesser::State::with_number(0, 100, 37);
+    state.guess(50).unwrap();
+    state.guess(25).unwrap();
+    let back = round_trip(&state);
+    assert_eq!(back.bounds(), (26, 49));
+    for (_, hint) in state.history() {
+        round_trip(&hint);
+    }
+}
+
+#[test]
+fn transcripts_round_trip() {
+    round_trip(&Transcript {
+        turns: vec![Turn {
+            seat: 0,
+            time: UNIX_EPOCH + Duration::from_millis(1_700_000_000_000),
+            spent: Duration::from_micros(1250),
+            mv: 4usize,
+        }],
+        outcome: Some(Outcome::Draw),
+        ..Transcript::new(&numberguesser::State::with_number(0, 10, 7))
+    });
+}
@@ -0,0 +1,91 @@
+mod common;
+
+use ax::numberguesser;
+use ax::seed::{Seed, Streams};
+use ax::snapshot::{self, Error};
+use ax::State;
+use rand::Rng;
+
+use common::board;
+
+fn saved<S: snapshot::Snapshot>(state: &S) -> String {
+    let mut out = Vec::new();
+    snapshot::write(&mut out, state, &Streams::new(Seed::new(Some(42)))).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn games_resume_where_they_were_saved() {
+    let played = board(&[4, 0, 8]);
+    let (resumed, streams) = snapshot::read(saved(&played).as_bytes(), board(&[])).unwrap();
+    assert_eq!(resumed, played);
+    assert_eq!(resumed.current_player(), 1);
+    assert_eq!(streams.seed(), Seed::new(Some(42)));
+
+    let mut guessing = numberguesser::State::with_number(0, 100, 37);
+    guessing.guess(50).unwrap();
+    guessing.guess(25).unwrap();
+    let new = numberguesser::State::with_number(0, 10, 0);
+    let (resumed, _) = snapshot::read(saved(&guessing).as_bytes(), new).unwrap();
+    assert_eq!(resumed, guessing);
+}
+
+#[test]
+fn random_numbers_pick_up_where_they_left_off() {
+    let streams = Streams::new(Seed::new(Some(42)));
+    let mut rng = streams.stream("tic-tac-toe seat 1");
+    let drawn: Vec<u64> = (0..5).map(|_| rng.gen()).collect();
+    streams.stream("tic-tac-toe seat 0");
+
+    let mut out = Vec::new();
+    snapshot::write(&mut out, &board(&[4]), &streams).unwrap();
+    let text = String::from_utf8(out).unwrap();
+    assert!(text.contains("\nrng 10 tic-tac-toe seat 1\nrng 0 tic-tac-toe seat 0\n"));
+
+    let (_, resumed) = snapshot::read(text.as_bytes(), board(&[])).unwrap();
+    let next: Vec<u64> = (0..5).map(|_| rng.gen()).collect();
+    let mut rng = resumed.stream("tic-tac-toe seat 1");
+    assert_eq!((0..5).map(|_| rng.gen()).collect::<Vec<u64>>(), next);
+    assert_ne!(next, drawn);
+}
+
+#[test]
+fn snapshots_of_other_games_are_rejected() {
+    let guessing = numberguesser::State::with_number(0, 100, 37);
+    let err = snapshot::read(saved(&guessing).as_bytes(), board(&[])).unwrap_err();
+    assert!(matches!(err, Error::Mismatch { .. }), "{:?}", err);
+    assert_eq!(
+        err.to_string(),
+        "this is a snapshot of number-guesser, not tic-tac-toe"
+    );
+}
+
+#[test]
+fn corrupt_snapshots_are_rejected() {
+    let valid = saved(&board(&[4, 0]));
+    for (text, reason) in &[
+        ("", "missing ax-snapshot header"),
+        ("ax-snapshot 1\n", "ax-snapshot 1 is not supported"),
+        (
+            &valid.replace("moves 4 0", "moves 4 4"),
+            "move 2 at 4 is not legal",
+        ),
+        (
+            &valid.replace("turn 0", "turn 1"),
+            "it should be seat 1's turn",
+        ),
+        (&valid.replace("seed 42", "seed x"), "line 3: invalid seed"),
+        (
+            &valid.replace("turn 0", "rng x\nturn 0"),
+            "line 4: invalid rng",
+        ),
+        (
+            &valid.replace("moves 4 0", "moves 0 3 1 4 2"),
+            "the game is already over",
+        ),
+    ] {
+        let err = snapshot::read(text.as_bytes(), board(&[])).unwrap_err();
+        assert!(matches!(err, Error::Corrupt(_)), "{:?}", err);
+        assert!(err.to_string().contains(reason), "{}", err);
+    }
+}
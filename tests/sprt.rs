@@ -0,0 +1,133 @@
+use std::path::PathBuf;
+
+use ax::players::Options;
+use ax::seed::{Seed, Streams};
+use ax::sprt::{self, Bounds, Error, Sprt, Verdict};
+use ax::tournament::Record;
+
+const BOUNDS: Bounds = Bounds {
+    elo0: 0.0,
+    elo1: 50.0,
+    alpha: 0.05,
+    beta: 0.05,
+};
+
+fn test(baseline: &str, candidate: &str) -> Sprt {
+    Sprt {
+        baseline: baseline.to_string(),
+        candidate: candidate.to_string(),
+        bounds: BOUNDS,
+        max_games: 200,
+        options: Options {
+            depth: 9,
+            iterations: 100,
+            exploration: 1.0,
+            model: PathBuf::from("missing.model"),
+            streams: Streams::new(Seed::new(Some(7))),
+        },
+    }
+}
+
+#[test]
+fn results_move_the_llr_towards_the_right_hypothesis() {
+    let (lower, upper) = (BOUNDS.lower(), BOUNDS.upper());
+    assert!((upper - 19f64.ln()).abs() < 1e-9);
+    assert!((lower + 19f64.ln()).abs() < 1e-9);
+
+    let record = |wins, draws, losses| Record {
+        wins,
+        draws,
+        losses,
+    };
+    let even = BOUNDS.llr(&record(100, 100, 100));
+    let ahead = BOUNDS.llr(&record(140, 100, 60));
+    assert!(even < 0.0 && ahead > upper);
+    assert_eq!(BOUNDS.verdict(ahead), Some(Verdict::H1));
+    assert_eq!(BOUNDS.verdict(lower), Some(Verdict::H0));
+    assert_eq!(BOUNDS.verdict(0.0), None);
+}
+
+#[test]
+fn records_of_a_single_result_still_reach_a_verdict() {
+    let record = |wins, draws, losses| Record {
+        wins,
+        draws,
+        losses,
+    };
+    let llr = |r| BOUNDS.llr(&r);
+    assert_eq!(BOUNDS.verdict(llr(record(20, 0, 0))), Some(Verdict::H1));
+    assert_eq!(BOUNDS.verdict(llr(record(0, 0, 20))), Some(Verdict::H0));
+    assert_eq!(BOUNDS.verdict(llr(record(0, 40, 0))), Some(Verdict::H0));
+    assert!(llr(record(0, 2, 0)) < 0.0);
+    assert!(llr(record(0, 2, 0)) > BOUNDS.lower());
+}
+
+#[test]
+fn bounds_must_make_sense() {
+    assert_eq!(BOUNDS.check(), Ok(()));
+    for bounds in &[
+        Bounds {
+            elo0: 10.0,
+            elo1: 0.0,
+            ..BOUNDS
+        },
+        Bounds {
+            elo1: 0.0,
+            ..BOUNDS
+        },
+        Bounds {
+            alpha: 0.0,
+            ..BOUNDS
+        },
+        Bounds {
+            beta: 1.5,
+            ..BOUNDS
+        },
+    ] {
+        assert!(bounds.check().is_err(), "{:?}", bounds);
+        let test = Sprt {
+            bounds: *bounds,
+            ..test("random", "negamax")
+        };
+        let res = test.run(|_| panic!("no games should be played"));
+        assert!(matches!(res, Err(Error::Bounds(_))), "{:?}", res);
+    }
+}
+
+#[test]
+fn elo_and_expected_score_are_inverses() {
+    for elo in &[-200.0, 0.0, 35.0, 400.0] {
+        assert!((sprt::elo(sprt::expected_score(*elo)) - elo).abs() < 1e-9);
+    }
+}
+
+#[test]
+fn stronger_candidates_are_accepted() {
+    let mut reports = 0;
+    let result = test("random", "negamax").run(|_| reports += 1).unwrap();
+    assert_eq!(result.verdict, Some(Verdict::H1));
+    assert_eq!(reports * 2, result.record.games());
+
+    let result = test("negamax", "random").run(|_| {}).unwrap();
+    assert_eq!(result.verdict, Some(Verdict::H0));
+}
+
+#[test]
+fn equal_candidates_are_rejected() {
+    let result = test("negamax", "negamax").run(|_| {}).unwrap();
+    assert_eq!(result.verdict, Some(Verdict::H0));
+    assert_eq!(result.record.draws, result.record.games());
+}
+
+#[test]
+fn tests_without_a_verdict_stop_at_the_limit() {
+    let mut reports = 0;
+    let test = Sprt {
+        max_games: 3,
+        ..test("negamax", "negamax")
+    };
+    let result = test.run(|_| reports += 1).unwrap();
+    assert_eq!(result.verdict, None);
+    assert_eq!(result.record.games(), 3);
+    assert_eq!(reports, 2);
+}
@@ -0,0 +1,22 @@
+use ax::tictactoe::{Board, Error};
+
+#[test]
+fn pieces_must_go_on_the_board() {
+    let mut board = Board::new('X', 'O');
+    assert!(matches!(board.place_piece(12), Err(Error::OutOfRange(12))));
+    assert!(matches!(board.place_piece(9), Err(Error::OutOfRange(9))));
+    assert_eq!(board, Board::new('X', 'O'));
+    assert_eq!(
+        Error::OutOfRange(12).to_string(),
+        "12 is not a position, pick one from 0 to 8"
+    );
+}
+
+#[test]
+fn pieces_cant_be_stacked() {
+    let mut board = Board::new('X', 'O');
+    board.place_piece(8).unwrap();
+    assert!(matches!(board.place_piece(8), Err(Error::SpotOccupied)));
+    assert_eq!(board[8], 'X');
+    assert_eq!(board.next_piece(), 'O');
+}
@@ -0,0 +1,97 @@
+use std::path::PathBuf;
+
+use ax::players::Options;
+use ax::seed::{Seed, Streams};
+use ax::tournament::{self, Error, Format, Pairing, Record, Tournament};
+
+fn tournament(ais: &[&str], threads: usize) -> Tournament {
+    Tournament {
+        ais: ais.iter().map(|ai| ai.to_string()).collect(),
+        games: 10,
+        threads,
+        options: Options {
+            depth: 9,
+            iterations: 1000,
+            exploration: std::f64::consts::SQRT_2,
+            model: PathBuf::from("tic-tac-toe.model"),
+            streams: Streams::new(Seed::new(Some(7))),
+        },
+    }
+}
+
+#[test]
+fn negamax_never_loses_to_random() {
+    let pairings = tournament(&["random", "negamax"], 2).run().unwrap();
+
+    assert_eq!(pairings.len(), 1);
+    let record = pairings[0].record;
+    assert_eq!(record.games(), 10);
+    assert_eq!(record.wins, 0, "random beat negamax: {:?}", record);
+}
+
+#[test]
+fn failing_games_are_reported() {
+    let humans = tournament(&["random", "human"], 1);
+    assert!(matches!(humans.run(), Err(Error::Human)));
+
+    // The model could go missing after the tournament checked it.
+    let mut missing = tournament(&["random", "nn"], 2);
+    missing.options.model = PathBuf::from("no such file.model");
+    assert!(matches!(missing.play((0, 1), 0), Err(Error::Player(_))));
+    assert!(matches!(missing.run(), Err(Error::Player(_))));
+}
+
+#[test]
+fn results_do_not_depend_on_threads() {
+    let ais = ["random", "mcts", "random"];
+    let pairings = tournament(&ais, 1).run().unwrap();
+
+    assert_eq!(pairings.len(), 3);
+    assert_eq!(tournament(&ais, 3).run().unwrap(), pairings);
+}
+
+#[test]
+fn standings_add_up_both_sides() {
+    let pairings = tournament(&["random", "negamax", "mcts"], 1).run().unwrap();
+    let standings = tournament::standings(&pairings);
+
+    let names: Vec<&str> = standings.iter().map(|(ai, _)| ai.as_str()).collect();
+    assert_eq!(names, vec!["random", "negamax", "mcts"]);
+    for (_, record) in &standings {
+        assert_eq!(record.games(), 20);
+    }
+    let wins: usize = standings.iter().map(|(_, r)| r.wins).sum();
+    let losses: usize = standings.iter().map(|(_, r)| r.losses).sum();
+    assert_eq!(wins, losses);
+}
+
+#[test]
+fn even_records_have_no_margin() {
+    let record = Record {
+        wins: 0,
+        draws: 10,
+        losses: 0,
+    };
+    assert_eq!(record.score(), 0.5);
+    assert_eq!(record.margin(), 0.0);
+}
+
+#[test]
+fn json_escapes_names() {
+    let pairing = Pairing {
+        ai: "models\\\"best\".model".to_string(),
+        opponent: "random".to_string(),
+        record: Record {
+            wins: 1,
+            draws: 0,
+            losses: 0,
+        },
+        games: Vec::new(),
+    };
+    let mut out = Vec::new();
+    tournament::write(&[pairing], Format::Json, &mut out).unwrap();
+
+    let json: serde_json::Value = serde_json::from_slice(&out).unwrap();
+    assert_eq!(json["pairings"][0]["ai"], "models\\\"best\".model");
+    assert_eq!(json["standings"][1]["wins"], 0);
+}
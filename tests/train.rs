@@ -0,0 +1,124 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::UNIX_EPOCH;
+
+use ax::ai::nn::{Activation, Loss, Optimizer};
+use ax::seed::Seed;
+use ax::tictactoe::Board;
+use ax::train::{self, Settings};
+use ax::transcript::{Transcript, Turn};
+use ax::{Game, Outcome};
+
+fn settings() -> Settings {
+    Settings {
+        epochs: 1,
+        games: 1,
+        population: 2,
+        layers: Vec::new(),
+        activation: Activation::Tanh,
+        loss: Loss::MeanSquared,
+        optimizer: Optimizer::sgd(0.01),
+        recorded: None,
+        seed: Seed::new(Some(1)),
+    }
+}
+
+/// Trains `model` with `alg` for tic-tac-toe, returning the error if any.
+fn train(model: &str, alg: &str, settings: &Settings) -> Result<(), String> {
+    static RUNS: AtomicUsize = AtomicUsize::new(0);
+    let run = RUNS.fetch_add(1, Ordering::Relaxed);
+    let dir = std::env::temp_dir().join(format!("ax-train-{}-{}", std::process::id(), run));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(format!("{}-{}.model", model, alg));
+    let res = train::train(model, alg, "tic-tac-toe", settings, &path);
+    let saved = path.exists();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(saved, res.is_ok());
+    res.map_err(|err| err.to_string())
+}
+
+#[test]
+fn neat_needs_a_population() {
+    assert_eq!(train("nueral-network", "neat", &settings()), Ok(()));
+
+    let empty = Settings {
+        population: 0,
+        ..settings()
+    };
+    assert_eq!(
+        train("nueral-network", "neat", &empty),
+        Err("a population needs at least one genome".to_string())
+    );
+}
+
+#[test]
+fn hidden_layers_need_nodes() {
+    let layers = Settings {
+        layers: vec![4, 0],
+        ..settings()
+    };
+    assert_eq!(
+        train("nueral-network", "backprop", &layers),
+        Err("hidden layers need at least one node".to_string())
+    );
+}
+
+/// Writes a transcript of each game in `games`, which start from a board
+/// with the first moves made and play the second.
+fn transcripts(games: &[(&[usize], &[usize], Option<Outcome>)]) -> Vec<u8> {
+    let mut out = Vec::new();
+    for (setup, moves, outcome) in games {
+        let mut start = Board::new('X', 'O');
+        for mv in *setup {
+            start.apply(*mv);
+        }
+        let turns = moves
+            .iter()
+            .enumerate()
+            .map(|(i, mv)| Turn {
+                seat: (setup.len() + i) % 2,
+                time: UNIX_EPOCH,
+                spent: Default::default(),
+                mv: *mv,
+            })
+            .collect();
+        Transcript {
+            turns,
+            outcome: outcome.clone(),
+            ..Transcript::new(&start)
+        }
+        .write(&mut out)
+        .unwrap();
+    }
+    out
+}
+
+/// Trains with backprop from the games in `text`, returning the error if any.
+fn train_from(text: &[u8]) -> Result<(), String> {
+    let path = std::env::temp_dir().join(format!("ax-train-from-{}", std::process::id()));
+    std::fs::write(&path, text).unwrap();
+    let from = Settings {
+        recorded: Some(path.clone()),
+        ..settings()
+    };
+    let res = train("nueral-network", "backprop", &from);
+    std::fs::remove_file(&path).unwrap();
+    res.map_err(|err| err.replace(&format!("{}: ", path.display()), ""))
+}
+
+#[test]
+fn backprop_learns_from_transcripts() {
+    let won: (&[usize], &[usize], _) = (&[], &[0, 3, 1, 4, 2], Some(Outcome::Winner(0)));
+    let cut_short: (&[usize], &[usize], _) = (&[4], &[0], None);
+    assert_eq!(train_from(&transcripts(&[won.clone(), cut_short])), Ok(()));
+
+    let illegal: (&[usize], &[usize], _) = (&[], &[4, 4], None);
+    assert_eq!(
+        train_from(&transcripts(&[won, illegal])),
+        Err("game 2: move 2: 4 is not a legal move".to_string())
+    );
+    assert_eq!(
+        train_from(b"4 0 8\n"),
+        Err("game 1: line 1: missing ax-transcript header".to_string())
+    );
+}
@@ -0,0 +1,144 @@
+use std::io;
+use std::time::{Duration, UNIX_EPOCH};
+
+use ax::combinator::{record, repeat_until_terminal, replay, turns};
+use ax::numberguesser;
+use ax::tictactoe::{Board, Random};
+use ax::transcript::{Transcript, Turn};
+use ax::{Action, Game, Outcome, State};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+fn turn(seat: usize, mv: usize) -> Turn<usize> {
+    Turn {
+        seat,
+        time: UNIX_EPOCH + Duration::from_millis(1_700_000_000_000),
+        spent: Duration::from_micros(250),
+        mv,
+    }
+}
+
+fn transcript(moves: &[usize], outcome: Option<Outcome>) -> Vec<u8> {
+    let transcript = Transcript {
+        turns: moves
+            .iter()
+            .enumerate()
+            .map(|(i, mv)| turn(i % 2, *mv))
+            .collect(),
+        outcome,
+        ..Transcript::new(&Board::new('X', 'O'))
+    };
+    let mut out = Vec::new();
+    transcript.write(&mut out).unwrap();
+    out
+}
+
+#[test]
+fn recorded_games_replay_to_the_same_position() {
+    for seed in 0..5 {
+        let players = vec![
+            Random::new(StdRng::seed_from_u64(seed)),
+            Random::new(StdRng::seed_from_u64(seed + 100)),
+        ];
+        let mut out = Vec::new();
+        let played = repeat_until_terminal(record(&mut out, turns(players)))
+            .apply(Board::new('X', 'O'))
+            .unwrap();
+
+        let replayed = replay(&out[..]).apply(Board::new('X', 'O')).unwrap();
+        assert_eq!(replayed, played);
+        assert!(replayed.status().unwrap().is_terminal());
+    }
+}
+
+#[test]
+fn transcripts_read_what_they_write() {
+    let games = [
+        Transcript {
+            turns: vec![turn(0, 4), turn(1, 0)],
+            outcome: Some(Outcome::Scores(vec![3, -1])),
+            ..Transcript::new(&Board::new('X', 'O'))
+        },
+        Transcript {
+            turns: vec![turn(0, 8)],
+            outcome: None,
+            ..Transcript::new(&numberguesser::State::with_number(0, 10, 7))
+        },
+    ];
+    let mut out = Vec::new();
+    for game in &games {
+        game.write(&mut out).unwrap();
+    }
+
+    let mut input = &out[..];
+    assert_eq!(Transcript::read(&mut input).unwrap(), games[0]);
+    assert_eq!(Transcript::read(&mut input).unwrap(), games[1]);
+
+    let err = Transcript::<usize>::read(&b"move 0 1 2 3\n"[..]).unwrap_err();
+    assert_eq!(err.to_string(), "line 1: missing ax-transcript header");
+    let err = Transcript::<usize>::read(&b"ax-transcript 1\ngame tic-tac-toe\n"[..]).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "line 1: transcript version 1 is not supported"
+    );
+    let err = Transcript::<usize>::read(&b"ax-transcript 2\nmove 0 1 2 3\n"[..]).unwrap_err();
+    assert_eq!(err.to_string(), "line 2: missing game");
+    let err = Transcript::<usize>::read(&b"ax-transcript 2\ngame tic-tac-toe\nmove 0 x 2 3\n"[..])
+        .unwrap_err();
+    assert_eq!(err.to_string(), "line 3: invalid number \"x\"");
+}
+
+#[test]
+fn games_replay_from_where_they_started() {
+    let mut state = numberguesser::State::with_number(0, 100, 42);
+    state.apply(50);
+    let mut out = Vec::new();
+    let played = repeat_until_terminal(record(&mut out, |mut state: numberguesser::State| {
+        let (low, high) = state.bounds();
+        state.apply((low + high) / 2);
+        Ok::<_, io::Error>(state)
+    }))
+    .apply(state)
+    .unwrap();
+
+    let text = String::from_utf8(out.clone()).unwrap();
+    assert!(text
+        .starts_with("ax-transcript 2\ngame number-guesser\nrange 0 100\nnumber 42\nguesses 50\n"));
+
+    let replayed = replay(&out[..])
+        .apply(numberguesser::State::with_number(0, 1, 0))
+        .unwrap();
+    assert_eq!(replayed, played);
+
+    let err = replay(&out[..]).apply(Board::new('X', 'O')).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "this is a transcript of number-guesser, not tic-tac-toe"
+    );
+}
+
+#[test]
+fn illegal_moves_are_rejected() {
+    let board = || Board::new('X', 'O');
+
+    let err = replay(&transcript(&[4, 4], None)[..])
+        .apply(board())
+        .unwrap_err();
+    assert_eq!(err.to_string(), "move 2: 4 is not a legal move");
+
+    let mut out = transcript(&[4], None);
+    out.extend_from_slice(b"move 0 0 0 0\n");
+    let err = replay(&out[..]).apply(board()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "move 2: seat 0 moved on the turn of seat 1"
+    );
+
+    let won = [0, 3, 1, 4, 2];
+    assert!(replay(&transcript(&won, Some(Outcome::Winner(0)))[..])
+        .apply(board())
+        .is_ok());
+    assert!(replay(&transcript(&won, Some(Outcome::Draw))[..])
+        .apply(board())
+        .is_err());
+}
@@ -0,0 +1,101 @@
+use std::io;
+use std::time::{Duration, UNIX_EPOCH};
+
+use ax::ai::Negamax;
+use ax::numberguesser;
+use ax::tictactoe::Board;
+use ax::transcript::{Transcript, Turn};
+use ax::viewer::{self, Viewer};
+use ax::{Outcome, State};
+
+fn viewer(moves: &[usize], outcome: Option<Outcome>) -> io::Result<Viewer<Board<char>, usize>> {
+    let transcript = Transcript {
+        turns: moves
+            .iter()
+            .enumerate()
+            .map(|(i, mv)| Turn {
+                seat: i % 2,
+                time: UNIX_EPOCH,
+                spent: Duration::from_millis(1),
+                mv: *mv,
+            })
+            .collect(),
+        outcome,
+        ..Transcript::new(&Board::new('X', 'O'))
+    };
+    Viewer::new(Board::new('X', 'O'), transcript)
+}
+
+fn shown(viewer: &Viewer<Board<char>, usize>) -> String {
+    let mut out = Vec::new();
+    viewer.show(&mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn steps_stay_within_the_game() {
+    let mut viewer = viewer(&[4, 0, 8], None).unwrap();
+    assert!(shown(&viewer).contains("Start of 3 moves"));
+
+    viewer.back();
+    viewer.next();
+    assert!(shown(&viewer).contains("Move 1 of 3: seat 0 played 4"));
+
+    viewer.jump(10);
+    assert!(shown(&viewer).contains("Move 3 of 3"));
+    assert!(shown(&viewer).contains("The game was cut short."));
+
+    let mut out = Vec::new();
+    viewer.jump(0);
+    viewer
+        .run(&b"2\nb\nnonsense\nq\nn\n"[..], &mut out, Duration::ZERO)
+        .unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains("Move 2 of 3"));
+    assert!(out.contains("Unknown command \"nonsense\""));
+    assert!(!out.contains("Move 3 of 3"));
+}
+
+#[test]
+fn illegal_moves_are_rejected() {
+    let err = viewer(&[4, 4], None).err().unwrap();
+    assert_eq!(err.to_string(), "move 2: 4 is not a legal move");
+
+    let mut transcript = Transcript::new(&Board::new('X', 'O'));
+    transcript.turns.push(Turn {
+        seat: 1,
+        time: UNIX_EPOCH,
+        spent: Duration::ZERO,
+        mv: 4,
+    });
+    let err = Viewer::new(Board::new('X', 'O'), transcript).err().unwrap();
+    assert_eq!(
+        err.to_string(),
+        "move 1: seat 1 moved on the turn of seat 0"
+    );
+
+    let guesses = Transcript::new(&numberguesser::State::with_number(0, 10, 3));
+    let err = Viewer::new(Board::new('X', 'O'), guesses).err().unwrap();
+    assert_eq!(
+        err.to_string(),
+        "this is a transcript of number-guesser, not tic-tac-toe"
+    );
+}
+
+#[test]
+fn blunders_are_pointed_out() {
+    // O lets X complete the top row instead of blocking it with 2.
+    let mut viewer = viewer(&[0, 4, 1, 8, 2], Some(Outcome::Winner(0))).unwrap();
+    let mut searches = [0, 1].map(|seat| {
+        Negamax::<(), _, _>::with_hueristic(seat, 9, move |b: &Board<char>| b.hueristic(seat))
+    });
+    viewer.evaluate(|board| viewer::evaluate(&mut searches[board.current_player()], board));
+
+    viewer.jump(3);
+    assert!(shown(&viewer).contains("as good as any other move"));
+    viewer.jump(4);
+    assert!(shown(&viewer).contains("?? 8 loses in 2, but 2"));
+    viewer.jump(5);
+    assert!(shown(&viewer).contains("2 wins in 1"));
+    assert!(shown(&viewer).contains("Seat 0 won."));
+}